@@ -1,11 +1,37 @@
 // Based on https://github.com/rust-lang/libz-sys/blob/1.0.25/build.rs
 
 use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn main() {
     println!("cargo:rerun-if-env-changed=LIBWEBP_SYS_STATIC");
+    println!("cargo:rerun-if-env-changed=LIBWEBP_SYS_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=LIBWEBP_SYS_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=LIBWEBP_SYS_SOURCE_DIR");
+    println!("cargo:rerun-if-env-changed=LIBWEBP_SYS_NO_PKG_CONFIG");
     println!("cargo:rerun-if-changed=build.rs");
+    if cfg!(feature = "runtime-loading") {
+        // Nothing to link: the `runtime` module opens libwebp with dlopen.
+        return;
+    }
+    if cfg!(feature = "prefix-symbols") {
+        // Only the bundled sources can be built with renamed symbols.
+        return build_libwebp();
+    }
+    let want_static =
+        cfg!(feature = "static") || env::var("LIBWEBP_SYS_STATIC").unwrap_or_default() == "1";
+    if let Some(lib_dir) = env::var_os("LIBWEBP_SYS_LIB_DIR") {
+        return link_prebuilt(Path::new(&lib_dir), want_static);
+    }
+    if env::var_os("LIBWEBP_SYS_SOURCE_DIR").is_some() {
+        return build_libwebp();
+    }
+    if cfg!(feature = "system-static") {
+        return link_system_static();
+    }
     let host = env::var("HOST").unwrap();
     let target = env::var("TARGET").unwrap();
     let host_and_target_contain = |s| host.contains(s) && target.contains(s);
@@ -14,31 +40,58 @@ fn main() {
     // also don't run pkg-config on macOS/FreeBSD/DragonFly. That'll end up printing
     // `-L /usr/lib` which wreaks havoc with linking to an OpenSSL in /usr/local/lib
     // (Homebrew, Ports, etc.)
-    let want_static =
-        cfg!(feature = "static") || env::var("LIBWEBP_SYS_STATIC").unwrap_or(String::new()) == "1";
-    if !want_static &&
-       !target.contains("msvc") && // pkg-config just never works here
-       !(host_and_target_contain("apple") ||
-         host_and_target_contain("freebsd") ||
-         host_and_target_contain("dragonfly"))
+    if !(want_static
+        || env::var("LIBWEBP_SYS_NO_PKG_CONFIG").unwrap_or_default() == "1"
+        || target.contains("msvc") // pkg-config just never works here
+        || host_and_target_contain("apple")
+        || host_and_target_contain("freebsd")
+        || host_and_target_contain("dragonfly"))
     {
         let mut config = pkg_config::Config::new();
         config.cargo_metadata(true);
-        if config.probe("libwebp").is_ok() {
-            if cfg!(feature = "demux") {
-                config.probe("libwebpdemux").unwrap();
+        if let Ok(library) = config.probe("libwebp") {
+            let version = Version::parse(&library.version);
+            match version {
+                Some(version) => check_version(version, "pkg-config"),
+                None => println!(
+                    "cargo:warning=could not parse libwebp version {:?} reported by pkg-config",
+                    library.version
+                ),
             }
-            if cfg!(feature = "mux") {
-                config.probe("libwebpmux").unwrap();
+            let mut include_paths = library.include_paths;
+            let mut components = vec![("webp", "webp".to_owned())];
+            let mut missing = Vec::new();
+            for (feature, package, name) in [
+                (cfg!(feature = "demux"), "libwebpdemux", "webpdemux"),
+                (cfg!(feature = "mux"), "libwebpmux", "webpmux"),
+                (cfg!(feature = "sharpyuv"), "libsharpyuv", "sharpyuv"),
+            ] {
+                if !feature {
+                    continue;
+                }
+                match config.probe(package) {
+                    Ok(library) => include_paths.extend(library.include_paths),
+                    Err(_) => missing.push(name),
+                }
+                components.push((name, name.to_owned()));
             }
+            include_paths.extend(build_missing(&missing, "pkg-config", version));
+            include_paths.dedup();
+            export(&Linked {
+                root: pkg_config::get_variable("libwebp", "prefix")
+                    .ok()
+                    .map(PathBuf::from),
+                include_paths,
+                is_static: false,
+                components,
+            });
             return;
         }
     }
 
-    if target.contains("msvc") {
-        if try_vcpkg() {
-            return;
-        }
+    if target.contains("msvc") && try_vcpkg() {
+        // vcpkg does not report versions; the port is expected to be recent.
+        return;
     }
 
     // Whitelist a bunch of situations where we build unconditionally.
@@ -61,186 +114,902 @@ fn main() {
     //
     // In any case test if zlib is actually installed and if so we link to it,
     // otherwise continue below to build things.
-    if libwebp_installed() {
-        println!("cargo:rustc-link-lib=webp");
-        if cfg!(feature = "demux") {
-            println!("cargo:rustc-link-lib=webpdemux");
+    if libwebp_installed(&["webp"]) {
+        let version = probe_installed_version(&["webp".to_owned()]);
+        match version {
+            Some(version) => check_version(version, "the installed headers"),
+            None => println!("cargo:warning=could not determine the installed libwebp version"),
+        }
+        let components = enabled_components();
+        let mut missing = Vec::new();
+        for (component, name) in &components {
+            if *component != "webp" && !libwebp_installed(&[name, "webp"]) {
+                missing.push(*component);
+            }
         }
-        if cfg!(feature = "mux") {
-            println!("cargo:rustc-link-lib=webpmux");
+        let include_paths = build_missing(&missing, "the compiler", version);
+        for (component, name) in &components {
+            if !missing.contains(component) {
+                println!("cargo:rustc-link-lib={name}");
+            }
         }
+        export(&Linked {
+            root: None,
+            include_paths,
+            is_static: false,
+            components,
+        });
         return;
     }
 
     build_libwebp()
 }
 
-fn build_libwebp() {
+/// Links statically against the `libwebp.a` found by pkg-config, along with
+/// the libraries it depends on (`Libs.private` and `Requires.private`).
+fn link_system_static() {
+    let mut config = pkg_config::Config::new();
+    config.statik(true).cargo_metadata(false);
+    // Probe the dependents first so that the libraries are listed in link
+    // order.
+    let mut packages = Vec::new();
+    for (feature, package) in [
+        (cfg!(feature = "demux"), "libwebpdemux"),
+        (cfg!(feature = "mux"), "libwebpmux"),
+        (true, "libwebp"),
+        (cfg!(feature = "sharpyuv"), "libsharpyuv"),
+    ] {
+        if feature {
+            match config.probe(package) {
+                Ok(library) => packages.push((package, library)),
+                Err(err) => panic!(
+                    "the system-static feature requires {package} to be found by pkg-config: {err}"
+                ),
+            }
+        }
+    }
+    // libwebp 1.3.0 and newer depend on libsharpyuv, which older .pc files
+    // may not declare.
+    if !cfg!(feature = "sharpyuv") {
+        if let Ok(library) = config.probe("libsharpyuv") {
+            packages.push(("libsharpyuv", library));
+        }
+    }
+
+    let webp = &packages
+        .iter()
+        .find(|(package, _)| *package == "libwebp")
+        .unwrap()
+        .1;
+    match Version::parse(&webp.version) {
+        Some(version) => check_version(version, "pkg-config"),
+        None => println!(
+            "cargo:warning=could not parse libwebp version {:?} reported by pkg-config",
+            webp.version
+        ),
+    }
+
+    // The static libraries are searched for in `libdir` too, since
+    // pkg-config leaves out system directories such as `/usr/lib`.
+    let mut search_paths = Vec::new();
+    let mut include_paths = Vec::new();
+    let mut libs = Vec::new();
+    for (package, library) in &packages {
+        let libdir = pkg_config::get_variable(package, "libdir")
+            .ok()
+            .map(PathBuf::from);
+        for path in library.link_paths.iter().cloned().chain(libdir) {
+            if !search_paths.contains(&path) {
+                search_paths.push(path);
+            }
+        }
+        for path in &library.include_paths {
+            if !include_paths.contains(path) {
+                include_paths.push(path.clone());
+            }
+        }
+        for lib in &library.libs {
+            if !libs.contains(lib) {
+                libs.push(lib.clone());
+            }
+        }
+    }
+    // Not every libwebp.pc lists `-lm` and `-lpthread` in `Libs.private`.
+    // Android and Apple targets have pthreads in libc.
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if target_cfg("FAMILY", "unix") {
+        let mut transitive = vec!["m"];
+        if !matches!(
+            target_os.as_str(),
+            "android" | "macos" | "ios" | "tvos" | "watchos" | "visionos"
+        ) {
+            transitive.push("pthread");
+        }
+        for lib in transitive {
+            if !libs.iter().any(|l| l == lib) {
+                libs.push(lib.to_owned());
+            }
+        }
+    }
+
+    for path in &search_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+    // The static libraries first, then the system libraries they need.
+    const COMPONENTS: [&str; 4] = ["webp", "webpdemux", "webpmux", "sharpyuv"];
+    let mut components = Vec::new();
+    for lib in &libs {
+        let Some(component) = COMPONENTS.into_iter().find(|component| component == lib) else {
+            continue;
+        };
+        if !search_paths
+            .iter()
+            .any(|dir| dir.join(format!("lib{lib}.a")).exists())
+        {
+            panic!(
+                "the system-static feature requires lib{lib}.a, but it is not in {:?}",
+                search_paths
+            );
+        }
+        println!("cargo:rustc-link-lib=static={lib}");
+        components.push((component, lib.clone()));
+    }
+    for lib in libs
+        .iter()
+        .filter(|lib| !COMPONENTS.contains(&lib.as_str()))
+    {
+        println!("cargo:rustc-link-lib={lib}");
+    }
+    export(&Linked {
+        root: pkg_config::get_variable("libwebp", "prefix")
+            .ok()
+            .map(PathBuf::from),
+        include_paths,
+        is_static: true,
+        components,
+    });
+}
+
+/// Links the libraries in `LIBWEBP_SYS_LIB_DIR`, whose headers are expected in
+/// `LIBWEBP_SYS_INCLUDE_DIR` or on the default search path.
+fn link_prebuilt(lib_dir: &Path, want_static: bool) {
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    let mut components = Vec::new();
+    for (component, _) in enabled_components() {
+        let name = prebuilt_name(lib_dir, component).unwrap_or_else(|| {
+            println!(
+                "cargo:warning=lib{component} was not found in LIBWEBP_SYS_LIB_DIR={}",
+                lib_dir.display()
+            );
+            component.to_owned()
+        });
+        components.push((component, name));
+    }
+    // A static libwebp 1.3.0 or newer depends on libsharpyuv.
+    if want_static && !cfg!(feature = "sharpyuv") {
+        if let Some(name) = prebuilt_name(lib_dir, "sharpyuv") {
+            components.push(("sharpyuv", name));
+        }
+    }
+    let kind = if want_static { "static=" } else { "" };
+    for (_, name) in &components {
+        println!("cargo:rustc-link-lib={kind}{name}");
+    }
+
+    let mut libs: Vec<String> = components.iter().map(|(_, name)| name.clone()).collect();
+    if want_static && target_cfg("FAMILY", "unix") {
+        libs.extend(["m".to_owned(), "pthread".to_owned()]);
+    }
+    match probe_installed_version(&libs) {
+        Some(version) => check_version(version, "LIBWEBP_SYS_LIB_DIR"),
+        None => {
+            println!("cargo:warning=could not determine the libwebp version in LIBWEBP_SYS_LIB_DIR")
+        }
+    }
+    export(&Linked {
+        root: None,
+        include_paths: env::var_os("LIBWEBP_SYS_INCLUDE_DIR")
+            .map(PathBuf::from)
+            .into_iter()
+            .collect(),
+        is_static: want_static,
+        components,
+    });
+}
+
+/// Returns the name to link `component` by if `lib_dir` contains it, e.g.
+/// `libwebp` for vcpkg-style `libwebp.lib`.
+fn prebuilt_name(lib_dir: &Path, component: &str) -> Option<String> {
+    let unix = ["a", "so", "dylib", "tbd"]
+        .iter()
+        .any(|ext| lib_dir.join(format!("lib{component}.{ext}")).exists());
+    if unix || lib_dir.join(format!("{component}.lib")).exists() {
+        Some(component.to_owned())
+    } else if lib_dir.join(format!("lib{component}.lib")).exists() {
+        Some(format!("lib{component}"))
+    } else {
+        None
+    }
+}
+
+fn check_build_allowed() {
     // For testing purpose
     if let Ok(value) = std::env::var("__LIBWEBP_SYS_FORBID_BUILD") {
         if value == "1" {
             panic!("__LIBWEBP_SYS_FORBID_BUILD is set to 1");
         }
     }
-    cc::Build::new()
-        // libwebpdecode_la_SOURCES (src/dec)
-        .file("c_src/src/dec/alpha_dec.c")
-        .file("c_src/src/dec/buffer_dec.c")
-        .file("c_src/src/dec/frame_dec.c")
-        .file("c_src/src/dec/idec_dec.c")
-        .file("c_src/src/dec/io_dec.c")
-        .file("c_src/src/dec/quant_dec.c")
-        .file("c_src/src/dec/tree_dec.c")
-        .file("c_src/src/dec/vp8_dec.c")
-        .file("c_src/src/dec/vp8l_dec.c")
-        .file("c_src/src/dec/webp_dec.c")
-        // libwebpencode_la_SOURCES (src/enc)
-        .file("c_src/src/enc/alpha_enc.c")
-        .file("c_src/src/enc/analysis_enc.c")
-        .file("c_src/src/enc/backward_references_cost_enc.c")
-        .file("c_src/src/enc/backward_references_enc.c")
-        .file("c_src/src/enc/config_enc.c")
-        .file("c_src/src/enc/cost_enc.c")
-        .file("c_src/src/enc/filter_enc.c")
-        .file("c_src/src/enc/frame_enc.c")
-        .file("c_src/src/enc/histogram_enc.c")
-        .file("c_src/src/enc/iterator_enc.c")
-        .file("c_src/src/enc/near_lossless_enc.c")
-        .file("c_src/src/enc/picture_enc.c")
-        .file("c_src/src/enc/picture_csp_enc.c")
-        .file("c_src/src/enc/picture_psnr_enc.c")
-        .file("c_src/src/enc/picture_rescale_enc.c")
-        .file("c_src/src/enc/picture_tools_enc.c")
-        .file("c_src/src/enc/predictor_enc.c")
-        .file("c_src/src/enc/quant_enc.c")
-        .file("c_src/src/enc/syntax_enc.c")
-        .file("c_src/src/enc/token_enc.c")
-        .file("c_src/src/enc/tree_enc.c")
-        .file("c_src/src/enc/vp8l_enc.c")
-        .file("c_src/src/enc/webp_enc.c")
-        // COMMON_SOURCES (src/dsp)
-        .file("c_src/src/dsp/alpha_processing.c")
-        .file("c_src/src/dsp/cpu.c")
-        .file("c_src/src/dsp/dec.c")
-        .file("c_src/src/dsp/dec_clip_tables.c")
-        .file("c_src/src/dsp/filters.c")
-        .file("c_src/src/dsp/lossless.c")
-        .file("c_src/src/dsp/rescaler.c")
-        .file("c_src/src/dsp/upsampling.c")
-        .file("c_src/src/dsp/yuv.c")
-        // ENC_SOURCES (src/dsp)
-        .file("c_src/src/dsp/cost.c")
-        .file("c_src/src/dsp/enc.c")
-        .file("c_src/src/dsp/lossless_enc.c")
-        .file("c_src/src/dsp/ssim.c")
-        // libwebpdspdecode_sse41_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/alpha_processing_sse41.c")
-        .file("c_src/src/dsp/dec_sse41.c")
-        .file("c_src/src/dsp/lossless_sse41.c")
-        .file("c_src/src/dsp/upsampling_sse41.c")
-        .file("c_src/src/dsp/yuv_sse41.c")
-        // libwebpdspdecode_sse2_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/alpha_processing_sse2.c")
-        .file("c_src/src/dsp/dec_sse2.c")
-        .file("c_src/src/dsp/filters_sse2.c")
-        .file("c_src/src/dsp/lossless_sse2.c")
-        .file("c_src/src/dsp/rescaler_sse2.c")
-        .file("c_src/src/dsp/upsampling_sse2.c")
-        .file("c_src/src/dsp/yuv_sse2.c")
-        // libwebpdspdecode_neon_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/alpha_processing_neon.c")
-        .file("c_src/src/dsp/dec_neon.c")
-        .file("c_src/src/dsp/filters_neon.c")
-        .file("c_src/src/dsp/lossless_neon.c")
-        .file("c_src/src/dsp/rescaler_neon.c")
-        .file("c_src/src/dsp/upsampling_neon.c")
-        .file("c_src/src/dsp/yuv_neon.c")
-        // libwebpdspdecode_msa_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/dec_msa.c")
-        .file("c_src/src/dsp/filters_msa.c")
-        .file("c_src/src/dsp/lossless_msa.c")
-        .file("c_src/src/dsp/rescaler_msa.c")
-        .file("c_src/src/dsp/upsampling_msa.c")
-        // libwebpdspdecode_mips32_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/dec_mips32.c")
-        .file("c_src/src/dsp/rescaler_mips32.c")
-        .file("c_src/src/dsp/yuv_mips32.c")
-        // libwebpdspdecode_mips_dsp_r2_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/alpha_processing_mips_dsp_r2.c")
-        .file("c_src/src/dsp/dec_mips_dsp_r2.c")
-        .file("c_src/src/dsp/filters_mips_dsp_r2.c")
-        .file("c_src/src/dsp/lossless_mips_dsp_r2.c")
-        .file("c_src/src/dsp/rescaler_mips_dsp_r2.c")
-        .file("c_src/src/dsp/upsampling_mips_dsp_r2.c")
-        .file("c_src/src/dsp/yuv_mips_dsp_r2.c")
-        // libwebpdsp_sse2_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/cost_sse2.c")
-        .file("c_src/src/dsp/enc_sse2.c")
-        .file("c_src/src/dsp/lossless_enc_sse2.c")
-        .file("c_src/src/dsp/ssim_sse2.c")
-        // libwebpdsp_sse41_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/enc_sse41.c")
-        .file("c_src/src/dsp/lossless_enc_sse41.c")
-        // libwebpdsp_neon_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/cost_neon.c")
-        .file("c_src/src/dsp/enc_neon.c")
-        .file("c_src/src/dsp/lossless_enc_neon.c")
-        // libwebpdsp_msa_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/enc_msa.c")
-        .file("c_src/src/dsp/lossless_enc_msa.c")
-        // libwebpdsp_mips32_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/cost_mips32.c")
-        .file("c_src/src/dsp/enc_mips32.c")
-        .file("c_src/src/dsp/lossless_enc_mips32.c")
-        // libwebpdsp_mips_dsp_r2_la_SOURCES (src/dsp)
-        .file("c_src/src/dsp/cost_mips_dsp_r2.c")
-        .file("c_src/src/dsp/enc_mips_dsp_r2.c")
-        .file("c_src/src/dsp/lossless_enc_mips_dsp_r2.c")
-        // COMMON_SOURCES (sharpyuv)
-        .file("c_src/sharpyuv/sharpyuv.c")
-        .file("c_src/sharpyuv/sharpyuv_cpu.c")
-        .file("c_src/sharpyuv/sharpyuv_csp.c")
-        .file("c_src/sharpyuv/sharpyuv_dsp.c")
-        .file("c_src/sharpyuv/sharpyuv_gamma.c")
-        .file("c_src/sharpyuv/sharpyuv_neon.c")
-        .file("c_src/sharpyuv/sharpyuv_sse2.c")
-        // COMMON_SOURCES (src/utils)
-        .file("c_src/src/utils/bit_reader_utils.c")
-        .file("c_src/src/utils/color_cache_utils.c")
-        .file("c_src/src/utils/filters_utils.c")
-        .file("c_src/src/utils/huffman_utils.c")
-        .file("c_src/src/utils/palette.c")
-        .file("c_src/src/utils/quant_levels_dec_utils.c")
-        .file("c_src/src/utils/rescaler_utils.c")
-        .file("c_src/src/utils/random_utils.c")
-        .file("c_src/src/utils/thread_utils.c")
-        .file("c_src/src/utils/utils.c")
-        // ENC_SOURCES (src/utils)
-        .file("c_src/src/utils/bit_writer_utils.c")
-        .file("c_src/src/utils/huffman_encode_utils.c")
-        .file("c_src/src/utils/quant_levels_utils.c")
-        .include("c_src")
+}
+
+/// The libwebp checkout to build from.
+fn source_dir() -> PathBuf {
+    match env::var_os("LIBWEBP_SYS_SOURCE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from("c_src"),
+    }
+}
+
+fn build_libwebp() {
+    check_build_allowed();
+    let source_dir = source_dir();
+    if let Some(version) = bundled_version(&source_dir) {
+        check_version(version, "the bundled sources");
+    }
+    let root = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let include = root.join("include");
+    let bundled = match symbol_prefix() {
+        Some(prefix) => {
+            // The sources are built twice: once as they are, to list the
+            // globals they define, and once with all of them renamed.
+            let scratch = root.join("unprefixed");
+            build_components(&Bundled::new(source_dir.clone(), None).unlinked(scratch.clone()));
+            let nm = nm();
+            let renames = symbol_renames(&prefix, &nm, &scratch);
+            // Lets the tests check the renamed libraries with the same tool.
+            println!("cargo:rustc-env=__LIBWEBP_SYS_NM={}", nm.display());
+            println!(
+                "cargo:rustc-env=__LIBWEBP_SYS_LIB_DIR={}",
+                root.join("lib").display()
+            );
+            copy_headers(&source_dir, &include, &renames);
+            Bundled::new(source_dir, Some(write_renames(&renames)))
+        }
+        None => {
+            copy_headers(&source_dir, &include, &[]);
+            Bundled::new(source_dir, None)
+        }
+    };
+    build_components(&bundled);
+    // Android and Apple targets have pthreads in libc.
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if use_threads()
+        && target_cfg("FAMILY", "unix")
+        && !matches!(
+            target_os.as_str(),
+            "android" | "macos" | "ios" | "tvos" | "watchos" | "visionos"
+        )
+    {
+        println!("cargo:rustc-link-lib=pthread");
+    }
+    // The encoder always needs libsharpyuv in a static build.
+    let mut components = enabled_components();
+    if !cfg!(feature = "sharpyuv") {
+        components.push(("sharpyuv", "sharpyuv".to_owned()));
+    }
+    // Reported by `threads_available`, and lets the tests check the build
+    // settings.
+    println!("cargo:rustc-env=__LIBWEBP_SYS_BUNDLED=1");
+    println!(
+        "cargo:rustc-env=__LIBWEBP_SYS_THREADS={}",
+        if use_threads() { 1 } else { 0 }
+    );
+    export(&Linked {
+        include_paths: vec![include.clone(), include.join("webp")],
+        root: Some(root),
+        is_static: true,
+        components,
+    });
+}
+
+/// Builds libwebp, libsharpyuv and the enabled libraries among libwebpdemux
+/// and libwebpmux.
+fn build_components(bundled: &Bundled) {
+    let objects = bundled.compile_simd(Simd::webp_sources);
+    bundled
+        .build()
+        .files(bundled.sources(&[
+            // libwebpdecode_la_SOURCES (src/dec)
+            "src/dec/alpha_dec.c",
+            "src/dec/buffer_dec.c",
+            "src/dec/frame_dec.c",
+            "src/dec/idec_dec.c",
+            "src/dec/io_dec.c",
+            "src/dec/quant_dec.c",
+            "src/dec/tree_dec.c",
+            "src/dec/vp8_dec.c",
+            "src/dec/vp8l_dec.c",
+            "src/dec/webp_dec.c",
+            // libwebpencode_la_SOURCES (src/enc)
+            "src/enc/alpha_enc.c",
+            "src/enc/analysis_enc.c",
+            "src/enc/backward_references_cost_enc.c",
+            "src/enc/backward_references_enc.c",
+            "src/enc/config_enc.c",
+            "src/enc/cost_enc.c",
+            "src/enc/filter_enc.c",
+            "src/enc/frame_enc.c",
+            "src/enc/histogram_enc.c",
+            "src/enc/iterator_enc.c",
+            "src/enc/near_lossless_enc.c",
+            "src/enc/picture_enc.c",
+            "src/enc/picture_csp_enc.c",
+            "src/enc/picture_psnr_enc.c",
+            "src/enc/picture_rescale_enc.c",
+            "src/enc/picture_tools_enc.c",
+            "src/enc/predictor_enc.c",
+            "src/enc/quant_enc.c",
+            "src/enc/syntax_enc.c",
+            "src/enc/token_enc.c",
+            "src/enc/tree_enc.c",
+            "src/enc/vp8l_enc.c",
+            "src/enc/webp_enc.c",
+            // COMMON_SOURCES (src/dsp)
+            "src/dsp/alpha_processing.c",
+            "src/dsp/cpu.c",
+            "src/dsp/dec.c",
+            "src/dsp/dec_clip_tables.c",
+            "src/dsp/filters.c",
+            "src/dsp/lossless.c",
+            "src/dsp/rescaler.c",
+            "src/dsp/upsampling.c",
+            "src/dsp/yuv.c",
+            // ENC_SOURCES (src/dsp)
+            "src/dsp/cost.c",
+            "src/dsp/enc.c",
+            "src/dsp/lossless_enc.c",
+            "src/dsp/ssim.c",
+            // COMMON_SOURCES (src/utils)
+            "src/utils/bit_reader_utils.c",
+            "src/utils/color_cache_utils.c",
+            "src/utils/filters_utils.c",
+            "src/utils/huffman_utils.c",
+            "src/utils/palette.c",
+            "src/utils/quant_levels_dec_utils.c",
+            "src/utils/rescaler_utils.c",
+            "src/utils/random_utils.c",
+            "src/utils/thread_utils.c",
+            "src/utils/utils.c",
+            // ENC_SOURCES (src/utils)
+            "src/utils/bit_writer_utils.c",
+            "src/utils/huffman_encode_utils.c",
+            "src/utils/quant_levels_utils.c",
+        ]))
+        .objects(objects)
         .compile("webp");
+    // The encoder depends on libsharpyuv, which is also exposed on its own
+    // through the `sharpyuv` feature.
+    build_sharpyuv(bundled);
     if cfg!(feature = "demux") {
-        cc::Build::new()
-            // libwebpdemux_la_SOURCES (src/demux)
-            .file("c_src/src/demux/anim_decode.c")
-            .file("c_src/src/demux/demux.c")
-            .include("c_src")
-            .compile("webpdemux");
+        build_demux(bundled);
     }
     if cfg!(feature = "mux") {
-        cc::Build::new()
+        build_mux(bundled);
+    }
+}
+
+/// Builds the components that `found_by` did not find next to the system
+/// libwebp from the bundled sources, and returns the include paths for their
+/// headers. Fails naming the missing packages unless the bundled sources are
+/// the same release as the system libwebp (`version`).
+fn build_missing(missing: &[&str], found_by: &str, version: Option<Version>) -> Vec<PathBuf> {
+    if missing.is_empty() {
+        return Vec::new();
+    }
+    let packages = missing
+        .iter()
+        .map(|name| format!("lib{name}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let source_dir = source_dir();
+    let bundled_version = bundled_version(&source_dir);
+    if bundled_version.is_none() || bundled_version != version {
+        let (was, them) = if missing.len() == 1 {
+            ("was", "it")
+        } else {
+            ("were", "them")
+        };
+        let reason = match (bundled_version, version) {
+            (None, _) => {
+                "the bundled sources are missing; try `git submodule update --init`".to_owned()
+            }
+            (Some(bundled), Some(version)) => {
+                format!("the bundled libwebp {bundled} does not match the system libwebp {version}")
+            }
+            (Some(_), None) => {
+                "the system libwebp version is unknown, so the bundled sources cannot be matched"
+                    .to_owned()
+            }
+        };
+        panic!(
+            "libwebp was found by {found_by}, but {packages} {was} not; install {them}, \
+             or disable the features that need {them} ({reason})"
+        );
+    }
+    check_build_allowed();
+    println!(
+        "cargo:warning={packages} not found by {found_by}; building from {}",
+        source_dir.display()
+    );
+    let include = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("include");
+    copy_headers(&source_dir, &include, &[]);
+    let bundled = Bundled::new(source_dir, None);
+    for name in missing {
+        match *name {
+            "webpdemux" => build_demux(&bundled),
+            "webpmux" => build_mux(&bundled),
+            "sharpyuv" => build_sharpyuv(&bundled),
+            _ => unreachable!("{name} is always installed"),
+        }
+    }
+    vec![include.clone(), include.join("webp")]
+}
+
+fn build_sharpyuv(bundled: &Bundled) {
+    let objects = bundled.compile_simd(Simd::sharpyuv_sources);
+    bundled
+        .build()
+        .files(bundled.sources(&[
+            // libsharpyuv_la_SOURCES (sharpyuv)
+            "sharpyuv/sharpyuv.c",
+            "sharpyuv/sharpyuv_cpu.c",
+            "sharpyuv/sharpyuv_csp.c",
+            "sharpyuv/sharpyuv_dsp.c",
+            "sharpyuv/sharpyuv_gamma.c",
+        ]))
+        .objects(objects)
+        .compile("sharpyuv");
+}
+
+fn build_demux(bundled: &Bundled) {
+    bundled
+        .build()
+        .files(bundled.sources(&[
+            // libwebpdemux_la_SOURCES (src/demux)
+            "src/demux/anim_decode.c",
+            "src/demux/demux.c",
+        ]))
+        .compile("webpdemux");
+}
+
+fn build_mux(bundled: &Bundled) {
+    bundled
+        .build()
+        .files(bundled.sources(&[
             // libwebpmux_la_SOURCES (src/mux)
-            .file("c_src/src/mux/anim_encode.c")
-            .file("c_src/src/mux/muxedit.c")
-            .file("c_src/src/mux/muxinternal.c")
-            .file("c_src/src/mux/muxread.c")
-            .include("c_src")
-            .compile("webpmux");
+            "src/mux/anim_encode.c",
+            "src/mux/muxedit.c",
+            "src/mux/muxinternal.c",
+            "src/mux/muxread.c",
+        ]))
+        .compile("webpmux");
+}
+
+/// Whether `CARGO_CFG_TARGET_<NAME>`, e.g. the target families or
+/// features, contains `value`.
+fn target_cfg(name: &str, value: &str) -> bool {
+    env::var(format!("CARGO_CFG_TARGET_{}", name))
+        .unwrap_or_default()
+        .split(',')
+        .any(|v| v == value)
+}
+
+/// Whether the bundled libwebp is built with threads. WebAssembly targets
+/// are always built single-threaded: wasip1 has no threads, and threads in
+/// emscripten only work in cross-origin isolated pages.
+fn use_threads() -> bool {
+    cfg!(feature = "threads") && env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default() != "wasm32"
+}
+
+/// A family of architecture-specific sources in the bundled libwebp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Simd {
+    Sse2,
+    Sse41,
+    Neon,
+    /// MIPS32, MIPS DSP R2 and MSA, which are only enabled when the target
+    /// itself supports them.
+    Mips,
+}
+
+impl Simd {
+    /// The families usable on the target, like upstream's `cmake/cpu.cmake`.
+    /// With `no-simd`, only the MIPS sources, which do not use intrinsics,
+    /// are kept.
+    fn for_target() -> Vec<Simd> {
+        let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+        let os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        let no_simd = cfg!(feature = "no-simd");
+        match arch.as_str() {
+            "x86" | "x86_64" if !no_simd => vec![Simd::Sse2, Simd::Sse41],
+            "aarch64" | "arm64ec" if !no_simd => vec![Simd::Neon],
+            "arm" if !no_simd && target_cfg("FEATURE", "neon") => vec![Simd::Neon],
+            // Emscripten implements the SSE intrinsics with SIMD128.
+            "wasm32" if !no_simd && os == "emscripten" && target_cfg("FEATURE", "simd128") => {
+                vec![Simd::Sse2, Simd::Sse41]
+            }
+            "mips" | "mips32r6" | "mips64" | "mips64r6" => vec![Simd::Mips],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The flag enabling the instruction set in the family's sources. Other
+    /// sources are compiled for the baseline target, and only call into
+    /// these after checking the CPU at runtime.
+    fn flag(self) -> Option<&'static str> {
+        match self {
+            Simd::Sse2 => Some("-msse2"),
+            Simd::Sse41 => Some("-msse4.1"),
+            Simd::Neon | Simd::Mips => None,
+        }
+    }
+
+    /// The `config.h` macro telling the other sources that the family's
+    /// code is available.
+    fn config_define(self) -> Option<&'static str> {
+        match self {
+            Simd::Sse2 => Some("WEBP_HAVE_SSE2"),
+            Simd::Sse41 => Some("WEBP_HAVE_SSE41"),
+            Simd::Neon => Some("WEBP_HAVE_NEON"),
+            Simd::Mips => None,
+        }
+    }
+
+    fn webp_sources(self) -> &'static [&'static str] {
+        match self {
+            Simd::Sse2 => &[
+                // libwebpdspdecode_sse2_la_SOURCES (src/dsp)
+                "src/dsp/alpha_processing_sse2.c",
+                "src/dsp/dec_sse2.c",
+                "src/dsp/filters_sse2.c",
+                "src/dsp/lossless_sse2.c",
+                "src/dsp/rescaler_sse2.c",
+                "src/dsp/upsampling_sse2.c",
+                "src/dsp/yuv_sse2.c",
+                // libwebpdsp_sse2_la_SOURCES (src/dsp)
+                "src/dsp/cost_sse2.c",
+                "src/dsp/enc_sse2.c",
+                "src/dsp/lossless_enc_sse2.c",
+                "src/dsp/ssim_sse2.c",
+            ],
+            Simd::Sse41 => &[
+                // libwebpdspdecode_sse41_la_SOURCES (src/dsp)
+                "src/dsp/alpha_processing_sse41.c",
+                "src/dsp/dec_sse41.c",
+                "src/dsp/lossless_sse41.c",
+                "src/dsp/upsampling_sse41.c",
+                "src/dsp/yuv_sse41.c",
+                // libwebpdsp_sse41_la_SOURCES (src/dsp)
+                "src/dsp/enc_sse41.c",
+                "src/dsp/lossless_enc_sse41.c",
+            ],
+            Simd::Neon => &[
+                // libwebpdspdecode_neon_la_SOURCES (src/dsp)
+                "src/dsp/alpha_processing_neon.c",
+                "src/dsp/dec_neon.c",
+                "src/dsp/filters_neon.c",
+                "src/dsp/lossless_neon.c",
+                "src/dsp/rescaler_neon.c",
+                "src/dsp/upsampling_neon.c",
+                "src/dsp/yuv_neon.c",
+                // libwebpdsp_neon_la_SOURCES (src/dsp)
+                "src/dsp/cost_neon.c",
+                "src/dsp/enc_neon.c",
+                "src/dsp/lossless_enc_neon.c",
+            ],
+            Simd::Mips => &[
+                // libwebpdspdecode_msa_la_SOURCES (src/dsp)
+                "src/dsp/dec_msa.c",
+                "src/dsp/filters_msa.c",
+                "src/dsp/lossless_msa.c",
+                "src/dsp/rescaler_msa.c",
+                "src/dsp/upsampling_msa.c",
+                // libwebpdspdecode_mips32_la_SOURCES (src/dsp)
+                "src/dsp/dec_mips32.c",
+                "src/dsp/rescaler_mips32.c",
+                "src/dsp/yuv_mips32.c",
+                // libwebpdspdecode_mips_dsp_r2_la_SOURCES (src/dsp)
+                "src/dsp/alpha_processing_mips_dsp_r2.c",
+                "src/dsp/dec_mips_dsp_r2.c",
+                "src/dsp/filters_mips_dsp_r2.c",
+                "src/dsp/lossless_mips_dsp_r2.c",
+                "src/dsp/rescaler_mips_dsp_r2.c",
+                "src/dsp/upsampling_mips_dsp_r2.c",
+                "src/dsp/yuv_mips_dsp_r2.c",
+                // libwebpdsp_msa_la_SOURCES (src/dsp)
+                "src/dsp/enc_msa.c",
+                "src/dsp/lossless_enc_msa.c",
+                // libwebpdsp_mips32_la_SOURCES (src/dsp)
+                "src/dsp/cost_mips32.c",
+                "src/dsp/enc_mips32.c",
+                "src/dsp/lossless_enc_mips32.c",
+                // libwebpdsp_mips_dsp_r2_la_SOURCES (src/dsp)
+                "src/dsp/cost_mips_dsp_r2.c",
+                "src/dsp/enc_mips_dsp_r2.c",
+                "src/dsp/lossless_enc_mips_dsp_r2.c",
+            ],
+        }
+    }
+
+    fn sharpyuv_sources(self) -> &'static [&'static str] {
+        match self {
+            Simd::Sse2 => &["sharpyuv/sharpyuv_sse2.c"],
+            Simd::Neon => &["sharpyuv/sharpyuv_neon.c"],
+            Simd::Sse41 | Simd::Mips => &[],
+        }
     }
 }
 
+/// Settings shared by every library built from the bundled sources.
+struct Bundled {
+    source_dir: PathBuf,
+    /// The header renaming the globals in a `prefix-symbols` build, included
+    /// before each source.
+    renames: Option<PathBuf>,
+    simd: Vec<Simd>,
+    /// The directory containing the generated `src/webp/config.h`.
+    config_dir: PathBuf,
+    out_dir: PathBuf,
+    /// Whether to link the libraries, as opposed to only building them.
+    link: bool,
+}
+
+impl Bundled {
+    fn new(source_dir: PathBuf, renames: Option<PathBuf>) -> Self {
+        let simd = Simd::for_target();
+        let config_dir = write_config(&simd);
+        Bundled {
+            source_dir,
+            renames,
+            simd,
+            config_dir,
+            out_dir: PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("lib"),
+            link: true,
+        }
+    }
+
+    /// Builds the libraries into `out_dir` without linking them.
+    fn unlinked(self, out_dir: PathBuf) -> Self {
+        Bundled {
+            out_dir,
+            link: false,
+            ..self
+        }
+    }
+
+    /// A `cc::Build` with the settings shared by every library.
+    fn build(&self) -> cc::Build {
+        let mut build = cc::Build::new();
+        build
+            .include(&self.source_dir)
+            .include(&self.config_dir)
+            .define("HAVE_CONFIG_H", None)
+            .out_dir(&self.out_dir)
+            .cargo_metadata(self.link);
+        if use_threads() {
+            build.define("WEBP_USE_THREAD", None);
+            if target_cfg("FAMILY", "unix") {
+                build.flag_if_supported("-pthread");
+            }
+        }
+        if !cfg!(feature = "no-simd")
+            && env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default() == "wasm32"
+            && target_cfg("FEATURE", "simd128")
+        {
+            build.flag("-msimd128");
+        }
+        if cfg!(feature = "no-simd") && self.simd.contains(&Simd::Mips) {
+            // MSA and DSP R2 are enabled by the target rather than config.h.
+            build.flag_if_supported("-mno-msa");
+            build.flag_if_supported("-mno-dspr2");
+        }
+        if let Some(renames) = &self.renames {
+            if build.get_compiler().is_like_msvc() {
+                build.flag(format!("/FI{}", renames.display()));
+            } else {
+                build.flag("-include").flag(renames);
+            }
+        }
+        build
+    }
+
+    /// Resolves paths relative to the source directory.
+    fn sources(&self, files: &[&str]) -> Vec<PathBuf> {
+        files
+            .iter()
+            .map(|file| self.source_dir.join(file))
+            .collect()
+    }
+
+    /// Compiles the sources of each family with its own flags, returning
+    /// the objects to add to the library.
+    fn compile_simd(&self, sources: fn(Simd) -> &'static [&'static str]) -> Vec<PathBuf> {
+        let mut objects = Vec::new();
+        for &simd in &self.simd {
+            if sources(simd).is_empty() {
+                continue;
+            }
+            let mut build = self.build();
+            if let Some(flag) = simd.flag() {
+                build.flag_if_supported(flag);
+            }
+            objects.extend(
+                build
+                    .files(self.sources(sources(simd)))
+                    .compile_intermediates(),
+            );
+        }
+        objects
+    }
+}
+
+/// Writes the `src/webp/config.h` read by the bundled sources, taking the
+/// place of the one upstream generates from `cmake/config.h.in`, and returns
+/// the directory to add to the include paths.
+fn write_config(simd: &[Simd]) -> PathBuf {
+    let dir = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("config");
+    let mut config = String::from("/* Generated by the libwebp-sys2 build script. */\n");
+    if env::var("CARGO_CFG_TARGET_ENDIAN").unwrap_or_default() == "big" {
+        config.push_str("#define WORDS_BIGENDIAN 1\n");
+    }
+    if !cc::Build::new().get_compiler().is_like_msvc() {
+        config.push_str("#define HAVE_BUILTIN_BSWAP16 1\n");
+        config.push_str("#define HAVE_BUILTIN_BSWAP32 1\n");
+        config.push_str("#define HAVE_BUILTIN_BSWAP64 1\n");
+    }
+    for define in simd.iter().filter_map(|simd| simd.config_define()) {
+        config.push_str(&format!("#define {} 1\n", define));
+    }
+    let path = dir.join("src/webp/config.h");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, config).unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+    dir
+}
+
+/// The prefix of the renamed symbols in a `prefix-symbols` build. The same
+/// prefix is passed to the `extern` blocks, which link against the renamed
+/// symbols.
+fn symbol_prefix() -> Option<String> {
+    if !cfg!(feature = "prefix-symbols") {
+        return None;
+    }
+    println!("cargo:rerun-if-env-changed=LIBWEBP_SYS_SYMBOL_PREFIX");
+    let prefix =
+        env::var("LIBWEBP_SYS_SYMBOL_PREFIX").unwrap_or_else(|_| "libwebp_sys_".to_owned());
+    if !is_c_identifier(&prefix) {
+        panic!(
+            "LIBWEBP_SYS_SYMBOL_PREFIX must be a C identifier, got {:?}",
+            prefix
+        );
+    }
+    println!("cargo:rustc-env=__LIBWEBP_SYS_SYMBOL_PREFIX={}", prefix);
+    println!("cargo:symbol_prefix={}", prefix);
+    Some(prefix)
+}
+
+fn is_c_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The `#define`s renaming every global defined by the libraries built into
+/// `lib_dir`: the API, but also the internal `VP8*` functions, the `WebP*`
+/// function pointers of the dsp code and the tables, any of which would
+/// clash with another libwebp linked into the same binary.
+fn symbol_renames(prefix: &str, nm: &Path, lib_dir: &Path) -> Vec<(String, String)> {
+    let mut archives = Vec::new();
+    let entries = fs::read_dir(lib_dir)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", lib_dir.display(), e));
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == "a" || ext == "lib")
+        {
+            archives.push(path);
+        }
+    }
+    let output = Command::new(nm)
+        .arg("-g")
+        .arg("-P")
+        .args(&archives)
+        .output()
+        .unwrap_or_else(|e| {
+            panic!(
+                "cannot run {} to list the symbols to rename; set NM to the target's nm: {}",
+                nm.display(),
+                e
+            )
+        });
+    if !output.status.success() {
+        panic!(
+            "{} failed to list the symbols to rename:\n{}",
+            nm.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    // C names get a leading underscore on Mach-O and 32-bit Windows.
+    let underscore =
+        target_cfg("VENDOR", "apple") || (target_cfg("OS", "windows") && target_cfg("ARCH", "x86"));
+    let mut names = Vec::new();
+    // Each line of the portable format reads `name type [value [size]]`,
+    // between `archive[object]:` headers.
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(kind)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        // Undefined, or weak and undefined.
+        if matches!(kind, "U" | "v" | "w") {
+            continue;
+        }
+        let name = match name.strip_prefix('_') {
+            Some(name) if underscore => name,
+            _ => name,
+        };
+        // Skips the compiler's own symbols, such as `__x86.get_pc_thunk.bx`.
+        if is_c_identifier(name) && !name.starts_with("__") {
+            names.push(name.to_owned());
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| {
+            let renamed = format!("{}{}", prefix, name);
+            (name, renamed)
+        })
+        .collect()
+}
+
+/// The target's `nm`, which is `NM`, or else named after the archiver `cc`
+/// uses, such as `aarch64-linux-gnu-nm` for `aarch64-linux-gnu-ar`.
+fn nm() -> PathBuf {
+    println!("cargo:rerun-if-env-changed=NM");
+    if let Some(nm) = env::var_os("NM") {
+        return PathBuf::from(nm);
+    }
+    let build = cc::Build::new();
+    if build.get_compiler().is_like_msvc() {
+        // `lib.exe` has no `nm` counterpart.
+        return PathBuf::from("llvm-nm");
+    }
+    let ar = PathBuf::from(build.get_archiver().get_program());
+    let stem = ar.file_stem().unwrap().to_string_lossy().into_owned();
+    match stem.strip_suffix("ar") {
+        Some(stem) => {
+            let nm = ar.with_file_name(format!("{}nm", stem));
+            match ar.extension() {
+                Some(extension) => nm.with_extension(extension),
+                None => nm,
+            }
+        }
+        None => PathBuf::from("nm"),
+    }
+}
+
+/// Writes the header included before each bundled source in a
+/// `prefix-symbols` build, and returns its path.
+fn write_renames(renames: &[(String, String)]) -> PathBuf {
+    let mut header = String::from("/* Generated by the libwebp-sys2 build script. */\n");
+    for (name, renamed) in renames {
+        header.push_str(&format!("#define {} {}\n", name, renamed));
+    }
+    let path = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("renames.h");
+    fs::write(&path, header).unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+    path
+}
+
 #[cfg(not(target_env = "msvc"))]
 fn try_vcpkg() -> bool {
     false
@@ -249,12 +1018,28 @@ fn try_vcpkg() -> bool {
 #[cfg(target_env = "msvc")]
 fn try_vcpkg() -> bool {
     // see if there is a vcpkg tree with libwebp installed
-    match vcpkg::Config::new()
-        .emit_includes(true)
-        .lib_name("libwebp")
-        .probe("libwebp")
-    {
-        Ok(_) => true,
+    let mut config = vcpkg::Config::new();
+    let components: Vec<_> = enabled_components()
+        .into_iter()
+        .map(|(component, name)| (component, format!("lib{}", name)))
+        .collect();
+    for (_, name) in &components {
+        config.lib_name(name);
+    }
+    match config.probe("libwebp") {
+        Ok(library) => {
+            export(&Linked {
+                root: library
+                    .include_paths
+                    .first()
+                    .and_then(|include| include.parent())
+                    .map(Path::to_path_buf),
+                include_paths: library.include_paths,
+                is_static: library.is_static,
+                components,
+            });
+            true
+        }
         Err(e) => {
             println!("note, vcpkg did not find libwebp: {}", e);
             false
@@ -262,13 +1047,13 @@ fn try_vcpkg() -> bool {
     }
 }
 
-fn libwebp_installed() -> bool {
+/// Whether the smoke test links against `libs`.
+fn libwebp_installed(libs: &[&str]) -> bool {
     let compiler = cc::Build::new().get_compiler();
     let mut cmd = Command::new(compiler.path());
-    cmd.arg("src/smoke.c")
-        .arg("-o")
-        .arg("/dev/null")
-        .arg("-lwebp");
+    cmd.arg("src/smoke.c").arg("-o").arg("/dev/null");
+    add_search_paths(&mut cmd);
+    cmd.args(libs.iter().map(|lib| format!("-l{lib}")));
 
     println!("running {:?}", cmd);
     if let Ok(status) = cmd.status() {
@@ -279,3 +1064,240 @@ fn libwebp_installed() -> bool {
 
     false
 }
+
+/// The libwebp components linked by the enabled features, with their
+/// library names.
+fn enabled_components() -> Vec<(&'static str, String)> {
+    let mut components = vec![("webp", "webp".to_owned())];
+    for (feature, name) in [
+        (cfg!(feature = "demux"), "webpdemux"),
+        (cfg!(feature = "mux"), "webpmux"),
+        (cfg!(feature = "sharpyuv"), "sharpyuv"),
+    ] {
+        if feature {
+            components.push((name, name.to_owned()));
+        }
+    }
+    components
+}
+
+/// What dependents need to compile against the libwebp being linked.
+struct Linked {
+    /// The installation prefix, when known.
+    root: Option<PathBuf>,
+    /// Include paths in addition to the compiler's default ones.
+    include_paths: Vec<PathBuf>,
+    is_static: bool,
+    /// Each linked component (`webp`, `webpdemux`, `webpmux` or `sharpyuv`)
+    /// with the name of its library.
+    components: Vec<(&'static str, String)>,
+}
+
+/// Exports `linked` to dependent build scripts as `DEP_WEBP_ROOT`,
+/// `DEP_WEBP_INCLUDE`, `DEP_WEBP_STATIC` and `DEP_WEBP_LIB_<COMPONENT>`, and
+/// tells the layout tests which compiler and include paths find the headers.
+fn export(linked: &Linked) {
+    if let Some(root) = &linked.root {
+        println!("cargo:root={}", root.display());
+    }
+    let include_paths = match env::join_paths(&linked.include_paths) {
+        Ok(paths) => paths.to_string_lossy().into_owned(),
+        Err(e) => {
+            println!("cargo:warning=cannot export include paths: {}", e);
+            String::new()
+        }
+    };
+    if !include_paths.is_empty() {
+        println!("cargo:include={}", include_paths);
+    }
+    println!("cargo:static={}", if linked.is_static { 1 } else { 0 });
+    for (component, name) in &linked.components {
+        println!("cargo:lib_{}={}", component, name);
+    }
+
+    let compiler = cc::Build::new().get_compiler();
+    println!(
+        "cargo:rustc-env=__LIBWEBP_SYS_CC={}",
+        compiler.path().display()
+    );
+    println!(
+        "cargo:rustc-env=__LIBWEBP_SYS_INCLUDE_PATHS={}",
+        include_paths
+    );
+}
+
+/// Installs the public headers of the sources in `source_dir` into `include`, laid
+/// out like upstream's `make install`. In a `prefix-symbols` build, each
+/// header starts with the `#define`s renaming the symbols it mentions, so
+/// that C code compiled against them links against the renamed symbols.
+fn copy_headers(source_dir: &Path, include: &Path, renames: &[(String, String)]) {
+    for (source, dest) in [
+        ("src/webp/decode.h", "webp/decode.h"),
+        ("src/webp/demux.h", "webp/demux.h"),
+        ("src/webp/encode.h", "webp/encode.h"),
+        ("src/webp/mux.h", "webp/mux.h"),
+        ("src/webp/mux_types.h", "webp/mux_types.h"),
+        ("src/webp/types.h", "webp/types.h"),
+        ("sharpyuv/sharpyuv.h", "webp/sharpyuv/sharpyuv.h"),
+        ("sharpyuv/sharpyuv_csp.h", "webp/sharpyuv/sharpyuv_csp.h"),
+    ] {
+        let dest = include.join(dest);
+        fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        let source = source_dir.join(source);
+        let header = fs::read_to_string(&source)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", source.display(), e));
+        let mut defines = String::new();
+        for (name, renamed) in renames {
+            if header.contains(name.as_str()) {
+                defines.push_str(&format!("#define {} {}\n", name, renamed));
+            }
+        }
+        fs::write(&dest, defines + &header)
+            .unwrap_or_else(|e| panic!("cannot write {}: {}", dest.display(), e));
+    }
+}
+
+/// A libwebp release number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl Version {
+    const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses `major.minor[.patch]`, ignoring any suffix on the last part.
+    fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().splitn(3, '.');
+        let mut next = |required: bool| match parts.next() {
+            Some(part) => {
+                let digits = part
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(part.len());
+                part[..digits].parse().ok()
+            }
+            None if required => None,
+            None => Some(0),
+        };
+        Some(Version::new(next(true)?, next(true)?, next(false)?))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The oldest libwebp release providing everything behind the enabled
+/// features, together with the newest of those features.
+fn required_version() -> Option<(Version, &'static str)> {
+    let required = if cfg!(feature = "1_5") {
+        Some((Version::new(1, 5, 0), "1_5"))
+    } else if cfg!(feature = "1_4") {
+        Some((Version::new(1, 4, 0), "1_4"))
+    } else if cfg!(feature = "1_2") {
+        Some((Version::new(1, 2, 0), "1_2"))
+    } else if cfg!(feature = "1_1") {
+        Some((Version::new(1, 1, 0), "1_1"))
+    } else if cfg!(feature = "0_6") {
+        Some((Version::new(0, 6, 0), "0_6"))
+    } else if cfg!(feature = "0_5") {
+        Some((Version::new(0, 5, 0), "0_5"))
+    } else {
+        None
+    };
+    // libsharpyuv was split out of libwebp in 1.3.0.
+    let sharpyuv = Version::new(1, 3, 0);
+    match required {
+        Some((version, _)) if version < sharpyuv && cfg!(feature = "sharpyuv") => {
+            Some((sharpyuv, "sharpyuv"))
+        }
+        None if cfg!(feature = "sharpyuv") => Some((sharpyuv, "sharpyuv")),
+        required => required,
+    }
+}
+
+/// Fails the build if `version` is older than the enabled features require,
+/// and exports it as `DEP_WEBP_VERSION` to dependent build scripts.
+fn check_version(version: Version, source: &str) {
+    if let Some((required, feature)) = required_version() {
+        if version < required {
+            // `1_2` is the only one of these in the default features.
+            let default = if feature == "1_2" {
+                " (it is a default feature, so this needs `default-features = false`)"
+            } else {
+                ""
+            };
+            panic!(
+                "libwebp {} was found via {}, but the `{}` feature requires libwebp {} or newer. \
+                 Remove the `{}` feature{}, or install a newer libwebp.",
+                version, source, feature, required, feature, default
+            );
+        }
+    }
+    println!("cargo:version={}", version);
+}
+
+/// Adds `LIBWEBP_SYS_INCLUDE_DIR` and `LIBWEBP_SYS_LIB_DIR` to a compiler
+/// command, with an rpath so that the result can run.
+fn add_search_paths(cmd: &mut Command) {
+    if let Some(dir) = env::var_os("LIBWEBP_SYS_INCLUDE_DIR") {
+        cmd.arg("-I").arg(dir);
+    }
+    if let Some(dir) = env::var_os("LIBWEBP_SYS_LIB_DIR") {
+        let mut rpath = std::ffi::OsString::from("-Wl,-rpath,");
+        rpath.push(&dir);
+        cmd.arg("-L").arg(dir).arg(rpath);
+    }
+}
+
+/// Compiles and runs a probe against the installed headers and library.
+fn probe_installed_version(libs: &[String]) -> Option<Version> {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR")?);
+    let exe = out_dir.join("version_probe");
+    let compiler = cc::Build::new().get_compiler();
+    if compiler.is_like_msvc() {
+        return None;
+    }
+    let mut cmd = compiler.to_command();
+    cmd.arg("src/version_probe.c").arg("-o").arg(&exe);
+    add_search_paths(&mut cmd);
+    cmd.args(libs.iter().map(|lib| format!("-l{lib}")));
+
+    println!("running {:?}", cmd);
+    if !cmd.status().ok()?.success() {
+        return None;
+    }
+    let output = Command::new(&exe).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Version::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads the version of the sources to build.
+fn bundled_version(source_dir: &Path) -> Option<Version> {
+    let path = source_dir.join("src/dec/vp8i_dec.h");
+    println!("cargo:rerun-if-changed={}", path.display());
+    let header = fs::read_to_string(path).ok()?;
+    let define = |name: &str| {
+        header.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("#define ")?.strip_prefix(name)?;
+            value.trim().parse().ok()
+        })
+    };
+    Some(Version::new(
+        define("DEC_MAJ_VERSION")?,
+        define("DEC_MIN_VERSION")?,
+        define("DEC_REV_VERSION")?,
+    ))
+}
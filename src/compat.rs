@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt;
+use std::os::raw::*;
+
+#[cfg(not(feature = "runtime-loading"))]
+use crate::*;
+
+// The oldest release providing everything behind the enabled version
+// features, packed like `WebPGetDecoderVersion`.
+cfg_if! {
+    if #[cfg(feature = "1_5")] {
+        pub(crate) const REQUIRED_VERSION: c_int = 0x010500;
+    } else if #[cfg(feature = "1_4")] {
+        pub(crate) const REQUIRED_VERSION: c_int = 0x010400;
+    } else if #[cfg(feature = "1_2")] {
+        pub(crate) const REQUIRED_VERSION: c_int = 0x010200;
+    } else if #[cfg(feature = "1_1")] {
+        pub(crate) const REQUIRED_VERSION: c_int = 0x010100;
+    } else if #[cfg(feature = "0_6")] {
+        pub(crate) const REQUIRED_VERSION: c_int = 0x000600;
+    } else if #[cfg(feature = "0_5")] {
+        pub(crate) const REQUIRED_VERSION: c_int = 0x000500;
+    } else {
+        pub(crate) const REQUIRED_VERSION: c_int = 0;
+    }
+}
+
+/// Formats a version packed as `0xMMmmpp`.
+pub(crate) struct PackedVersion(pub(crate) c_int);
+
+impl fmt::Display for PackedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = self.0;
+        write!(f, "{}.{}.{}", (v >> 16) & 0xff, (v >> 8) & 0xff, v & 0xff)
+    }
+}
+
+/// A library older than the enabled version features require, as reported
+/// by [`check_runtime_compat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeCompatError {
+    library: &'static str,
+    found: c_int,
+    required: c_int,
+}
+
+impl RuntimeCompatError {
+    /// The library that is too old, e.g. `"libwebpmux"`.
+    pub fn library(&self) -> &'static str {
+        self.library
+    }
+
+    /// The version reported by the library, packed as `0xMMmmpp`.
+    pub fn found(&self) -> c_int {
+        self.found
+    }
+
+    /// The version the enabled features require, packed as `0xMMmmpp`.
+    pub fn required(&self) -> c_int {
+        self.required
+    }
+}
+
+impl fmt::Display for RuntimeCompatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} is loaded, but the enabled features require {} or newer; \
+             its structs may not match the Rust definitions",
+            self.library,
+            PackedVersion(self.found),
+            PackedVersion(self.required)
+        )
+    }
+}
+
+impl Error for RuntimeCompatError {}
+
+#[cfg(not(feature = "runtime-loading"))]
+fn check(library: &'static str, found: c_int) -> Result<(), RuntimeCompatError> {
+    if found < REQUIRED_VERSION {
+        return Err(RuntimeCompatError {
+            library,
+            found,
+            required: REQUIRED_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the libraries loaded at runtime are at least as new as the
+/// enabled version features require.
+///
+/// The build script checks the libwebp found at build time, but a different
+/// shared library may be picked up when the program runs. Calling into an
+/// older one would read and write structs with the wrong layout; this
+/// compares `WebPGetDecoderVersion`, `WebPGetEncoderVersion` and, when
+/// enabled, `WebPGetDemuxVersion` and `WebPGetMuxVersion` instead.
+#[cfg(not(feature = "runtime-loading"))]
+pub fn check_runtime_compat() -> Result<(), RuntimeCompatError> {
+    check("libwebp (decoder)", unsafe { WebPGetDecoderVersion() })?;
+    check("libwebp (encoder)", unsafe { WebPGetEncoderVersion() })?;
+    #[cfg(feature = "demux")]
+    check("libwebpdemux", unsafe { WebPGetDemuxVersion() })?;
+    #[cfg(feature = "mux")]
+    check("libwebpmux", unsafe { WebPGetMuxVersion() })?;
+    Ok(())
+}
@@ -311,7 +311,7 @@ pub struct WebPDecoderConfig {
     pub options: WebPDecoderOptions,
 }
 
-unsafe extern "C" {
+webp_extern! {
     /// Return the decoder's version number, packed in hexadecimal using 8bits for
     /// each of major/minor/revision. E.g: v2.5.7 is 0x020507.
     pub fn WebPGetDecoderVersion() -> c_int;
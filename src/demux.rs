@@ -123,7 +123,7 @@ pub struct WebPAnimInfo {
     pub pad: [u32; 4],
 }
 
-unsafe extern "C" {
+webp_extern! {
     pub fn WebPGetDemuxVersion() -> c_int;
     #[doc(hidden)]
     #[must_use]
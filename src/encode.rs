@@ -389,7 +389,7 @@ pub struct WebPPicture {
     pub pad7: [*mut c_void; 2],
 }
 
-unsafe extern "C" {
+webp_extern! {
     /// Return the encoder's version number, packed in hexadecimal using 8bits for
     /// each of major/minor/revision. E.g: v2.5.7 is 0x020507.
     pub fn WebPGetEncoderVersion() -> c_int;
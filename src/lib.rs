@@ -5,6 +5,27 @@
 #[macro_use]
 extern crate cfg_if;
 
+/// Declares the functions of an `extern "C"` block, linking them under the
+/// renamed symbols of a `prefix-symbols` build.
+macro_rules! webp_extern {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($args:tt)*) $(-> $ret:ty)?;
+    )*) => {
+        unsafe extern "C" {
+            $(
+                $(#[$attr])*
+                #[cfg_attr(
+                    feature = "prefix-symbols",
+                    link_name = concat!(env!("__LIBWEBP_SYS_SYMBOL_PREFIX"), stringify!($name))
+                )]
+                $vis fn $name($($args)*) $(-> $ret)?;
+            )*
+        }
+    };
+}
+
+pub use crate::compat::*;
 pub use crate::decode::*;
 #[cfg(feature = "demux")]
 pub use crate::demux::*;
@@ -13,21 +34,51 @@ pub use crate::encode::*;
 pub use crate::mux::*;
 #[cfg(any(feature = "mux", feature = "demux"))]
 pub use crate::mux_types::*;
+#[cfg(feature = "sharpyuv")]
+pub use crate::sharpyuv::*;
+#[cfg(not(feature = "runtime-loading"))]
+pub use crate::threads::*;
 pub use crate::types::*;
 
+mod compat;
+// The raw bindings mirror the C headers, which document the contracts of
+// their inline functions.
+#[allow(clippy::missing_safety_doc)]
 mod decode;
 #[cfg(feature = "demux")]
+#[allow(clippy::missing_safety_doc)]
 mod demux;
+#[allow(clippy::missing_safety_doc)]
 mod encode;
 #[cfg(feature = "mux")]
+#[allow(clippy::missing_safety_doc)]
 mod mux;
 #[cfg(any(feature = "mux", feature = "demux"))]
+#[allow(clippy::missing_safety_doc)]
 mod mux_types;
+#[cfg(all(feature = "runtime-loading", unix))]
+pub mod runtime;
+#[cfg(feature = "safe")]
+pub mod safe;
+#[cfg(feature = "sharpyuv")]
+#[allow(clippy::missing_safety_doc)]
+mod sharpyuv;
+#[cfg(not(feature = "runtime-loading"))]
+mod threads;
 mod types;
 
 #[cfg(not(feature = "std"))]
 compile_error!("`std` feature is currently required");
 
+#[cfg(all(feature = "runtime-loading", not(unix)))]
+compile_error!("`runtime-loading` feature is only supported on Unix");
+
+#[cfg(all(feature = "runtime-loading", feature = "safe"))]
+compile_error!("`safe` feature cannot be combined with `runtime-loading`");
+
+#[cfg(all(feature = "runtime-loading", feature = "prefix-symbols"))]
+compile_error!("`prefix-symbols` feature cannot be combined with `runtime-loading`");
+
 #[allow(unused)]
 fn ensure_rust_1_85() {
     let _ = 0_u32.midpoint(2);
@@ -105,7 +105,7 @@ pub struct WebPAnimEncoderOptions {
     pub padding: [u32; 4],
 }
 
-unsafe extern "C" {
+webp_extern! {
     pub fn WebPGetMuxVersion() -> c_int;
     #[doc(hidden)]
     #[must_use]
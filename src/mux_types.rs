@@ -84,5 +84,5 @@ pub unsafe extern "C" fn WebPDataCopy(src: *const WebPData, dst: *mut WebPData)
             (*dst).size = (*src).size;
         }
     }
-    return 1;
+    1
 }
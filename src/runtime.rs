@@ -0,0 +1,939 @@
+//! Loading libwebp with `dlopen` when the program starts, instead of linking
+//! against it.
+//!
+//! With the `runtime-loading` feature, the build script emits no link
+//! directives. [`Library::load`] opens `libwebp`, checks its version against
+//! the enabled version features, and resolves every function of the `decode`
+//! and `encode` modules into a [`WebPFunctions`] table. `libwebpdemux` and
+//! `libwebpmux` are loaded too when the `demux` and `mux` features are
+//! enabled, but their absence is not an error.
+//!
+//! The extern functions of the crate root stay declared, but must not be
+//! called in this mode, as nothing provides their symbols. The inline
+//! wrappers such as `WebPInitDecoderConfig` are not part of the
+//! table; call the `*Internal` functions with the `*_ABI_VERSION` constants
+//! instead.
+//!
+//! ```no_run
+//! use libwebp_sys::runtime::Library;
+//!
+//! let library = Library::global().expect("libwebp is not available");
+//! let webp = library.webp();
+//! let version = unsafe { (webp.WebPGetDecoderVersion)() };
+//! if library.mux().is_none() {
+//!     eprintln!("libwebpmux is not installed; animation encoding is disabled");
+//! }
+//! ```
+
+use std::error::Error;
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::fmt;
+use std::mem;
+use std::os::raw::*;
+use std::os::unix::ffi::OsStrExt;
+use std::ptr::NonNull;
+use std::sync::OnceLock;
+
+use crate::compat::{PackedVersion, REQUIRED_VERSION};
+use crate::*;
+
+#[cfg(target_vendor = "apple")]
+const WEBP_NAMES: &[&str] = &["libwebp.7.dylib", "libwebp.dylib"];
+#[cfg(not(target_vendor = "apple"))]
+const WEBP_NAMES: &[&str] = &["libwebp.so.7", "libwebp.so"];
+#[cfg(all(feature = "demux", target_vendor = "apple"))]
+const DEMUX_NAMES: &[&str] = &["libwebpdemux.2.dylib", "libwebpdemux.dylib"];
+#[cfg(all(feature = "demux", not(target_vendor = "apple")))]
+const DEMUX_NAMES: &[&str] = &["libwebpdemux.so.2", "libwebpdemux.so"];
+#[cfg(all(feature = "mux", target_vendor = "apple"))]
+const MUX_NAMES: &[&str] = &["libwebpmux.3.dylib", "libwebpmux.dylib"];
+#[cfg(all(feature = "mux", not(target_vendor = "apple")))]
+const MUX_NAMES: &[&str] = &["libwebpmux.so.3", "libwebpmux.so"];
+
+/// An error from [`Library::load`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadError {
+    /// `dlopen` failed for every candidate name.
+    Open {
+        /// The last name tried.
+        library: String,
+        /// The message from `dlerror`.
+        message: String,
+    },
+    /// A required function is missing from the library.
+    MissingSymbol {
+        /// The name the library was opened as.
+        library: String,
+        /// The function that could not be resolved.
+        symbol: &'static str,
+    },
+    /// The library is older than the enabled version features require.
+    VersionTooOld {
+        library: String,
+        /// The version reported by the library, packed as `0xMMmmpp`.
+        found: c_int,
+        /// The required version, packed as `0xMMmmpp`.
+        required: c_int,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Open { library, message } => {
+                write!(f, "failed to load {}: {}", library, message)
+            }
+            LoadError::MissingSymbol { library, symbol } => {
+                write!(f, "{} does not provide {}", library, symbol)
+            }
+            LoadError::VersionTooOld {
+                library,
+                found,
+                required,
+            } => write!(
+                f,
+                "{} is version {}, but the enabled features require {} or newer",
+                library,
+                PackedVersion(*found),
+                PackedVersion(*required)
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// A handle returned by `dlopen`, closed on drop.
+#[derive(Debug)]
+struct Handle {
+    handle: NonNull<c_void>,
+    name: String,
+}
+
+// dlopen handles are not tied to a thread.
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
+impl Handle {
+    /// Opens the first of `names` that `dlopen` accepts.
+    fn open(names: &[OsString]) -> Result<Self, LoadError> {
+        let mut error = None;
+        for name in names {
+            let display = name.to_string_lossy().into_owned();
+            let Ok(c_name) = CString::new(name.as_bytes()) else {
+                error = Some(LoadError::Open {
+                    library: display,
+                    message: "path contains a NUL byte".to_owned(),
+                });
+                continue;
+            };
+            let handle =
+                unsafe { libc::dlopen(c_name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+            match NonNull::new(handle) {
+                Some(handle) => {
+                    return Ok(Handle {
+                        handle,
+                        name: display,
+                    });
+                }
+                None => {
+                    error = Some(LoadError::Open {
+                        library: display,
+                        message: dlerror(),
+                    });
+                }
+            }
+        }
+        Err(error.unwrap_or_else(|| LoadError::Open {
+            library: String::new(),
+            message: "no library name given".to_owned(),
+        }))
+    }
+
+    fn symbol_address(&self, name: &CStr) -> Option<NonNull<c_void>> {
+        NonNull::new(unsafe { libc::dlsym(self.handle.as_ptr(), name.as_ptr()) })
+    }
+
+    /// # Safety
+    ///
+    /// `F` must be a function pointer type matching the symbol.
+    unsafe fn optional<F: Copy>(&self, name: &'static str) -> Option<F> {
+        assert_eq!(mem::size_of::<F>(), mem::size_of::<*mut c_void>());
+        let c_name = CStr::from_bytes_with_nul(name.as_bytes()).unwrap();
+        let address = self.symbol_address(c_name)?;
+        Some(unsafe { mem::transmute_copy::<NonNull<c_void>, F>(&address) })
+    }
+
+    /// # Safety
+    ///
+    /// `F` must be a function pointer type matching the symbol.
+    unsafe fn required<F: Copy>(&self, name: &'static str) -> Result<F, LoadError> {
+        unsafe { self.optional(name) }.ok_or_else(|| LoadError::MissingSymbol {
+            library: self.name.clone(),
+            symbol: name.trim_end_matches('\0'),
+        })
+    }
+
+    fn check_version(&self, version: c_int) -> Result<(), LoadError> {
+        if version < REQUIRED_VERSION {
+            return Err(LoadError::VersionTooOld {
+                library: self.name.clone(),
+                found: version,
+                required: REQUIRED_VERSION,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe { libc::dlclose(self.handle.as_ptr()) };
+    }
+}
+
+fn dlerror() -> String {
+    let message = unsafe { libc::dlerror() };
+    if message.is_null() {
+        return "unknown error".to_owned();
+    }
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+macro_rules! function_table {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[cfg($($cfg:tt)*)])*
+                pub fn $fn:ident($($arg:tt: $ty:ty),* $(,)?) $(-> $ret:ty)?;
+            )*
+        }
+        $(
+            optional {
+                $(
+                    $(#[cfg($($opt_cfg:tt)*)])*
+                    pub fn $opt_fn:ident($($opt_arg:tt: $opt_ty:ty),* $(,)?)
+                        $(-> $opt_ret:ty)?;
+                )*
+            }
+        )?
+    ) => {
+        $(#[$meta])*
+        #[allow(non_snake_case)]
+        pub struct $name {
+            $(
+                $(#[cfg($($cfg)*)])*
+                pub $fn: unsafe extern "C" fn($($ty),*) $(-> $ret)?,
+            )*
+            $($(
+                $(#[cfg($($opt_cfg)*)])*
+                pub $opt_fn: Option<unsafe extern "C" fn($($opt_ty),*) $(-> $opt_ret)?>,
+            )*)?
+        }
+
+        impl $name {
+            /// # Safety
+            ///
+            /// The library must be a libwebp matching the declarations.
+            unsafe fn load(handle: &Handle) -> Result<Self, LoadError> {
+                Ok($name {
+                    $(
+                        $(#[cfg($($cfg)*)])*
+                        $fn: unsafe { handle.required(concat!(stringify!($fn), "\0"))? },
+                    )*
+                    $($(
+                        $(#[cfg($($opt_cfg)*)])*
+                        $opt_fn: unsafe { handle.optional(concat!(stringify!($opt_fn), "\0")) },
+                    )*)?
+                })
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+function_table! {
+    /// The functions of `libwebp`, as declared in the `decode`, `encode` and
+    /// `types` modules.
+    pub struct WebPFunctions {
+        pub fn WebPGetDecoderVersion() -> c_int;
+        pub fn WebPGetInfo(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> c_int;
+        pub fn WebPDecodeRGBA(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeARGB(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeBGRA(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeRGB(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeBGR(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeYUV(
+            data: *const u8,
+            data_size: usize,
+            width: *mut c_int,
+            height: *mut c_int,
+            u: *mut *mut u8,
+            v: *mut *mut u8,
+            stride: *mut c_int,
+            uv_stride: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeRGBAInto(
+            data: *const u8,
+            data_size: usize,
+            output_buffer: *mut u8,
+            output_buffer_size: usize,
+            output_stride: c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeARGBInto(
+            data: *const u8,
+            data_size: usize,
+            output_buffer: *mut u8,
+            output_buffer_size: usize,
+            output_stride: c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeBGRAInto(
+            data: *const u8,
+            data_size: usize,
+            output_buffer: *mut u8,
+            output_buffer_size: usize,
+            output_stride: c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeRGBInto(
+            data: *const u8,
+            data_size: usize,
+            output_buffer: *mut u8,
+            output_buffer_size: usize,
+            output_stride: c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeBGRInto(
+            data: *const u8,
+            data_size: usize,
+            output_buffer: *mut u8,
+            output_buffer_size: usize,
+            output_stride: c_int,
+        ) -> *mut u8;
+        pub fn WebPDecodeYUVInto(
+            data: *const u8,
+            data_size: usize,
+            luma: *mut u8,
+            luma_size: usize,
+            luma_stride: c_int,
+            u: *mut u8,
+            u_size: usize,
+            u_stride: c_int,
+            v: *mut u8,
+            v_size: usize,
+            v_stride: c_int,
+        ) -> *mut u8;
+        pub fn WebPInitDecBufferInternal(_: *mut WebPDecBuffer, _: c_int) -> c_int;
+        pub fn WebPFreeDecBuffer(buffer: *mut WebPDecBuffer);
+        pub fn WebPINewDecoder(output_buffer: *mut WebPDecBuffer) -> *mut WebPIDecoder;
+        pub fn WebPINewRGB(
+            csp: WEBP_CSP_MODE,
+            output_buffer: *mut u8,
+            output_buffer_size: usize,
+            output_stride: c_int,
+        ) -> *mut WebPIDecoder;
+        pub fn WebPINewYUVA(
+            luma: *mut u8,
+            luma_size: usize,
+            luma_stride: c_int,
+            u: *mut u8,
+            u_size: usize,
+            u_stride: c_int,
+            v: *mut u8,
+            v_size: usize,
+            v_stride: c_int,
+            a: *mut u8,
+            a_size: usize,
+            a_stride: c_int,
+        ) -> *mut WebPIDecoder;
+        pub fn WebPINewYUV(
+            luma: *mut u8,
+            luma_size: usize,
+            luma_stride: c_int,
+            u: *mut u8,
+            u_size: usize,
+            u_stride: c_int,
+            v: *mut u8,
+            v_size: usize,
+            v_stride: c_int,
+        ) -> *mut WebPIDecoder;
+        pub fn WebPIDelete(idec: *mut WebPIDecoder);
+        pub fn WebPIAppend(idec: *mut WebPIDecoder, data: *const u8, data_size: usize)
+        -> VP8StatusCode;
+        pub fn WebPIUpdate(idec: *mut WebPIDecoder, data: *const u8, data_size: usize)
+        -> VP8StatusCode;
+        pub fn WebPIDecGetRGB(
+            idec: *const WebPIDecoder,
+            last_y: *mut c_int,
+            width: *mut c_int,
+            height: *mut c_int,
+            stride: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPIDecGetYUVA(
+            idec: *const WebPIDecoder,
+            last_y: *mut c_int,
+            u: *mut *mut u8,
+            v: *mut *mut u8,
+            a: *mut *mut u8,
+            width: *mut c_int,
+            height: *mut c_int,
+            stride: *mut c_int,
+            uv_stride: *mut c_int,
+            a_stride: *mut c_int,
+        ) -> *mut u8;
+        pub fn WebPIDecodedArea(
+            idec: *const WebPIDecoder,
+            left: *mut c_int,
+            top: *mut c_int,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> *const WebPDecBuffer;
+        pub fn WebPGetFeaturesInternal(
+            _: *const u8,
+            _: usize,
+            _: *mut WebPBitstreamFeatures,
+            _: c_int,
+        ) -> VP8StatusCode;
+        pub fn WebPInitDecoderConfigInternal(_: *mut WebPDecoderConfig, _: c_int) -> c_int;
+        pub fn WebPIDecode(
+            data: *const u8,
+            data_size: usize,
+            config: *mut WebPDecoderConfig,
+        ) -> *mut WebPIDecoder;
+        pub fn WebPDecode(
+            data: *const u8,
+            data_size: usize,
+            config: *mut WebPDecoderConfig,
+        ) -> VP8StatusCode;
+        pub fn WebPGetEncoderVersion() -> c_int;
+        pub fn WebPEncodeRGB(
+            rgb: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            quality_factor: c_float,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeBGR(
+            bgr: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            quality_factor: c_float,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeRGBA(
+            rgba: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            quality_factor: c_float,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeBGRA(
+            bgra: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            quality_factor: c_float,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeLosslessRGB(
+            rgb: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeLosslessBGR(
+            bgr: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeLosslessRGBA(
+            rgba: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPEncodeLosslessBGRA(
+            bgra: *const u8,
+            width: c_int,
+            height: c_int,
+            stride: c_int,
+            output: *mut *mut u8,
+        ) -> usize;
+        pub fn WebPConfigInitInternal(_: *mut WebPConfig, _: WebPPreset, _: c_float, _: c_int)
+        -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPConfigLosslessPreset(config: *mut WebPConfig, level: c_int) -> c_int;
+        pub fn WebPValidateConfig(config: *const WebPConfig) -> c_int;
+        pub fn WebPMemoryWriterInit(writer: *mut WebPMemoryWriter);
+        #[cfg(feature = "0_5")]
+        pub fn WebPMemoryWriterClear(writer: *mut WebPMemoryWriter);
+        pub fn WebPMemoryWrite(data: *const u8, data_size: usize, picture: *const WebPPicture)
+        -> c_int;
+        pub fn WebPPictureInitInternal(_: *mut WebPPicture, _: c_int) -> c_int;
+        pub fn WebPPictureAlloc(picture: *mut WebPPicture) -> c_int;
+        pub fn WebPPictureFree(picture: *mut WebPPicture);
+        pub fn WebPPictureCopy(src: *const WebPPicture, dst: *mut WebPPicture) -> c_int;
+        #[cfg(feature = "0_6")]
+        pub fn WebPPlaneDistortion(
+            src: *const u8,
+            src_stride: usize,
+            ref_: *const u8,
+            ref_stride: usize,
+            width: c_int,
+            height: c_int,
+            x_step: usize,
+            type_: c_int,
+            distortion: *mut c_float,
+            result: *mut c_float,
+        ) -> c_int;
+        pub fn WebPPictureDistortion(
+            src: *const WebPPicture,
+            ref_: *const WebPPicture,
+            metric_type: c_int,
+            result: *mut c_float,
+        ) -> c_int;
+        pub fn WebPPictureCrop(
+            picture: *mut WebPPicture,
+            left: c_int,
+            top: c_int,
+            width: c_int,
+            height: c_int,
+        ) -> c_int;
+        pub fn WebPPictureView(
+            src: *const WebPPicture,
+            left: c_int,
+            top: c_int,
+            width: c_int,
+            height: c_int,
+            dst: *mut WebPPicture,
+        ) -> c_int;
+        pub fn WebPPictureIsView(picture: *const WebPPicture) -> c_int;
+        pub fn WebPPictureRescale(picture: *mut WebPPicture, width: c_int, height: c_int) -> c_int;
+        pub fn WebPPictureImportRGB(
+            picture: *mut WebPPicture,
+            rgb: *const u8,
+            rgb_stride: c_int,
+        ) -> c_int;
+        pub fn WebPPictureImportRGBA(
+            picture: *mut WebPPicture,
+            rgba: *const u8,
+            rgba_stride: c_int,
+        ) -> c_int;
+        pub fn WebPPictureImportRGBX(
+            picture: *mut WebPPicture,
+            rgbx: *const u8,
+            rgbx_stride: c_int,
+        ) -> c_int;
+        pub fn WebPPictureImportBGR(
+            picture: *mut WebPPicture,
+            bgr: *const u8,
+            bgr_stride: c_int,
+        ) -> c_int;
+        pub fn WebPPictureImportBGRA(
+            picture: *mut WebPPicture,
+            bgra: *const u8,
+            bgra_stride: c_int,
+        ) -> c_int;
+        pub fn WebPPictureImportBGRX(
+            picture: *mut WebPPicture,
+            bgrx: *const u8,
+            bgrx_stride: c_int,
+        ) -> c_int;
+        pub fn WebPPictureARGBToYUVA(picture: *mut WebPPicture, colorspace: WebPEncCSP) -> c_int;
+        pub fn WebPPictureARGBToYUVADithered(
+            picture: *mut WebPPicture,
+            colorspace: WebPEncCSP,
+            dithering: c_float,
+        ) -> c_int;
+        #[cfg(feature = "0_6")]
+        pub fn WebPPictureSharpARGBToYUVA(picture: *mut WebPPicture) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPPictureSmartARGBToYUVA(picture: *mut WebPPicture) -> c_int;
+        pub fn WebPPictureYUVAToARGB(picture: *mut WebPPicture) -> c_int;
+        pub fn WebPCleanupTransparentArea(picture: *mut WebPPicture);
+        pub fn WebPPictureHasTransparency(picture: *const WebPPicture) -> c_int;
+        pub fn WebPBlendAlpha(picture: *mut WebPPicture, background_rgb: u32);
+        pub fn WebPEncode(config: *const WebPConfig, picture: *mut WebPPicture) -> c_int;
+        #[cfg(feature = "1_1")]
+        pub fn WebPMalloc(size: usize) -> *mut c_void;
+        #[cfg(feature = "0_5")]
+        pub fn WebPFree(ptr: *mut c_void);
+    }
+}
+
+#[cfg(feature = "demux")]
+function_table! {
+    /// The functions of `libwebpdemux`, as declared in the `demux` module.
+    pub struct DemuxFunctions {
+        pub fn WebPGetDemuxVersion() -> c_int;
+        pub fn WebPDemuxInternal(
+            _: *const WebPData,
+            _: c_int,
+            _: *mut WebPDemuxState,
+            _: c_int,
+        ) -> *mut WebPDemuxer;
+        pub fn WebPDemuxDelete(dmux: *mut WebPDemuxer);
+        pub fn WebPDemuxGetI(dmux: *const WebPDemuxer, feature: WebPFormatFeature) -> u32;
+        pub fn WebPDemuxGetFrame(
+            dmux: *const WebPDemuxer,
+            frame_number: c_int,
+            iter: *mut WebPIterator,
+        ) -> c_int;
+        pub fn WebPDemuxNextFrame(iter: *mut WebPIterator) -> c_int;
+        pub fn WebPDemuxPrevFrame(iter: *mut WebPIterator) -> c_int;
+        pub fn WebPDemuxReleaseIterator(iter: *mut WebPIterator);
+        pub fn WebPDemuxGetChunk(
+            dmux: *const WebPDemuxer,
+            fourcc: *const c_char,
+            chunk_number: c_int,
+            iter: *mut WebPChunkIterator,
+        ) -> c_int;
+        pub fn WebPDemuxNextChunk(iter: *mut WebPChunkIterator) -> c_int;
+        pub fn WebPDemuxPrevChunk(iter: *mut WebPChunkIterator) -> c_int;
+        pub fn WebPDemuxReleaseChunkIterator(iter: *mut WebPChunkIterator);
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderOptionsInitInternal(
+            _: *mut WebPAnimDecoderOptions,
+            _: c_int,
+        ) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderNewInternal(
+            _: *const WebPData,
+            _: *const WebPAnimDecoderOptions,
+            _: c_int,
+        ) -> *mut WebPAnimDecoder;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderGetInfo(
+            dec: *const WebPAnimDecoder,
+            info: *mut WebPAnimInfo,
+        ) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderGetNext(
+            dec: *mut WebPAnimDecoder,
+            buf: *mut *mut u8,
+            timestamp: *mut c_int,
+        ) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderHasMoreFrames(dec: *const WebPAnimDecoder) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderReset(dec: *mut WebPAnimDecoder);
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderGetDemuxer(dec: *const WebPAnimDecoder) -> *const WebPDemuxer;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimDecoderDelete(dec: *mut WebPAnimDecoder);
+    }
+    optional {
+        // Removed as of libwebp 0.5.0.
+        #[cfg(not(feature = "0_5"))]
+        pub fn WebPDemuxSelectFragment(iter: *mut WebPIterator, fragment_num: c_int) -> c_int;
+    }
+}
+
+#[cfg(feature = "mux")]
+function_table! {
+    /// The functions of `libwebpmux`, as declared in the `mux` module.
+    pub struct MuxFunctions {
+        pub fn WebPGetMuxVersion() -> c_int;
+        pub fn WebPNewInternal(_: c_int) -> *mut WebPMux;
+        pub fn WebPMuxDelete(mux: *mut WebPMux);
+        pub fn WebPMuxCreateInternal(_: *const WebPData, _: c_int, _: c_int) -> *mut WebPMux;
+        pub fn WebPMuxSetChunk(
+            mux: *mut WebPMux,
+            fourcc: *const c_char,
+            chunk_data: *const WebPData,
+            copy_data: c_int,
+        ) -> WebPMuxError;
+        pub fn WebPMuxGetChunk(
+            mux: *const WebPMux,
+            fourcc: *const c_char,
+            chunk_data: *mut WebPData,
+        ) -> WebPMuxError;
+        pub fn WebPMuxDeleteChunk(mux: *mut WebPMux, fourcc: *const c_char) -> WebPMuxError;
+        pub fn WebPMuxSetImage(
+            mux: *mut WebPMux,
+            bitstream: *const WebPData,
+            copy_data: c_int,
+        ) -> WebPMuxError;
+        pub fn WebPMuxPushFrame(
+            mux: *mut WebPMux,
+            frame: *const WebPMuxFrameInfo,
+            copy_data: c_int,
+        ) -> WebPMuxError;
+        pub fn WebPMuxGetFrame(
+            mux: *const WebPMux,
+            nth: u32,
+            frame: *mut WebPMuxFrameInfo,
+        ) -> WebPMuxError;
+        pub fn WebPMuxDeleteFrame(mux: *mut WebPMux, nth: u32) -> WebPMuxError;
+        pub fn WebPMuxSetAnimationParams(
+            mux: *mut WebPMux,
+            params: *const WebPMuxAnimParams,
+        ) -> WebPMuxError;
+        pub fn WebPMuxGetAnimationParams(
+            mux: *const WebPMux,
+            params: *mut WebPMuxAnimParams,
+        ) -> WebPMuxError;
+        #[cfg(feature = "0_5")]
+        pub fn WebPMuxSetCanvasSize(mux: *mut WebPMux, width: c_int, height: c_int) -> WebPMuxError;
+        pub fn WebPMuxGetCanvasSize(
+            mux: *const WebPMux,
+            width: *mut c_int,
+            height: *mut c_int,
+        ) -> WebPMuxError;
+        pub fn WebPMuxGetFeatures(mux: *const WebPMux, flags: *mut u32) -> WebPMuxError;
+        pub fn WebPMuxNumChunks(
+            mux: *const WebPMux,
+            id: WebPChunkId,
+            num_elements: *mut c_int,
+        ) -> WebPMuxError;
+        pub fn WebPMuxAssemble(mux: *mut WebPMux, assembled_data: *mut WebPData) -> WebPMuxError;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimEncoderOptionsInitInternal(
+            _: *mut WebPAnimEncoderOptions,
+            _: c_int,
+        ) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimEncoderNewInternal(
+            _: c_int,
+            _: c_int,
+            _: *const WebPAnimEncoderOptions,
+            _: c_int,
+        ) -> *mut WebPAnimEncoder;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimEncoderAdd(
+            enc: *mut WebPAnimEncoder,
+            frame: *mut WebPPicture,
+            timestamp_ms: c_int,
+            config: *const WebPConfig,
+        ) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimEncoderAssemble(
+            enc: *mut WebPAnimEncoder,
+            webp_data: *mut WebPData,
+        ) -> c_int;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimEncoderGetError(enc: *mut WebPAnimEncoder) -> *const c_char;
+        #[cfg(feature = "0_5")]
+        pub fn WebPAnimEncoderDelete(enc: *mut WebPAnimEncoder);
+        #[cfg(feature = "1_4")]
+        pub fn WebPAnimEncoderSetChunk(
+            enc: *mut WebPAnimEncoder,
+            fourcc: *const c_char,
+            chunk_data: *const WebPData,
+            copy_data: c_int,
+        ) -> WebPMuxError;
+        #[cfg(feature = "1_4")]
+        pub fn WebPAnimEncoderGetChunk(
+            enc: *const WebPAnimEncoder,
+            fourcc: *const c_char,
+            chunk_data: *mut WebPData,
+        ) -> WebPMuxError;
+        #[cfg(feature = "1_4")]
+        pub fn WebPAnimEncoderDeleteChunk(
+            enc: *mut WebPAnimEncoder,
+            fourcc: *const c_char,
+        ) -> WebPMuxError;
+    }
+}
+
+/// Where [`Library::load_with`] looks for the libraries.
+///
+/// Each library is searched under its usual names by default, so only
+/// non-standard locations need to be set.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryPaths {
+    webp: Option<OsString>,
+    #[cfg(feature = "demux")]
+    demux: Option<OsString>,
+    #[cfg(feature = "mux")]
+    mux: Option<OsString>,
+}
+
+impl LibraryPaths {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name or path of `libwebp`.
+    pub fn webp(mut self, path: impl AsRef<OsStr>) -> Self {
+        self.webp = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the name or path of `libwebpdemux`. Unlike the default names,
+    /// an explicit path that fails to load is an error.
+    #[cfg(feature = "demux")]
+    pub fn demux(mut self, path: impl AsRef<OsStr>) -> Self {
+        self.demux = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the name or path of `libwebpmux`. Unlike the default names, an
+    /// explicit path that fails to load is an error.
+    #[cfg(feature = "mux")]
+    pub fn mux(mut self, path: impl AsRef<OsStr>) -> Self {
+        self.mux = Some(path.as_ref().to_owned());
+        self
+    }
+}
+
+fn candidates(path: &Option<OsString>, defaults: &[&str]) -> Vec<OsString> {
+    match path {
+        Some(path) => vec![path.clone()],
+        None => defaults.iter().map(OsString::from).collect(),
+    }
+}
+
+/// Opens an optional library. Only a library that could not be found under
+/// its default names is treated as absent.
+#[cfg(any(feature = "demux", feature = "mux"))]
+fn open_optional(path: &Option<OsString>, defaults: &[&str]) -> Result<Option<Handle>, LoadError> {
+    match Handle::open(&candidates(path, defaults)) {
+        Ok(handle) => Ok(Some(handle)),
+        Err(_) if path.is_none() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The libwebp libraries, loaded at runtime.
+///
+/// The function tables are only accessible through a borrow of the library,
+/// as the libraries are closed on drop.
+#[derive(Debug)]
+pub struct Library {
+    webp: WebPFunctions,
+    #[cfg(feature = "demux")]
+    demux: Option<DemuxFunctions>,
+    #[cfg(feature = "mux")]
+    mux: Option<MuxFunctions>,
+    // Declared last so that the tables are gone before the libraries are
+    // closed.
+    handles: Vec<Handle>,
+}
+
+impl Library {
+    /// Loads the libraries from their usual names.
+    pub fn load() -> Result<Self, LoadError> {
+        Self::load_with(&LibraryPaths::new())
+    }
+
+    /// Loads the libraries from the given locations.
+    pub fn load_with(paths: &LibraryPaths) -> Result<Self, LoadError> {
+        let mut handles = Vec::new();
+
+        let handle = Handle::open(&candidates(&paths.webp, WEBP_NAMES))?;
+        let webp = unsafe { WebPFunctions::load(&handle)? };
+        handle.check_version(unsafe { (webp.WebPGetDecoderVersion)() })?;
+        handle.check_version(unsafe { (webp.WebPGetEncoderVersion)() })?;
+        handles.push(handle);
+
+        #[cfg(feature = "demux")]
+        let demux = match open_optional(&paths.demux, DEMUX_NAMES)? {
+            Some(handle) => {
+                let demux = unsafe { DemuxFunctions::load(&handle)? };
+                handle.check_version(unsafe { (demux.WebPGetDemuxVersion)() })?;
+                handles.push(handle);
+                Some(demux)
+            }
+            None => None,
+        };
+
+        #[cfg(feature = "mux")]
+        let mux = match open_optional(&paths.mux, MUX_NAMES)? {
+            Some(handle) => {
+                let mux = unsafe { MuxFunctions::load(&handle)? };
+                handle.check_version(unsafe { (mux.WebPGetMuxVersion)() })?;
+                handles.push(handle);
+                Some(mux)
+            }
+            None => None,
+        };
+
+        Ok(Library {
+            webp,
+            #[cfg(feature = "demux")]
+            demux,
+            #[cfg(feature = "mux")]
+            mux,
+            handles,
+        })
+    }
+
+    /// A process-wide library, loaded with [`Library::load`] on first use.
+    pub fn global() -> Result<&'static Library, &'static LoadError> {
+        static LIBRARY: OnceLock<Result<Library, LoadError>> = OnceLock::new();
+        LIBRARY.get_or_init(Library::load).as_ref()
+    }
+
+    /// The functions of `libwebp`.
+    pub fn webp(&self) -> &WebPFunctions {
+        &self.webp
+    }
+
+    /// The functions of `libwebpdemux`, or `None` if it is not installed.
+    #[cfg(feature = "demux")]
+    pub fn demux(&self) -> Option<&DemuxFunctions> {
+        self.demux.as_ref()
+    }
+
+    /// The functions of `libwebpmux`, or `None` if it is not installed.
+    #[cfg(feature = "mux")]
+    pub fn mux(&self) -> Option<&MuxFunctions> {
+        self.mux.as_ref()
+    }
+
+    /// True if any of the loaded libraries exports `symbol`. This covers
+    /// functions of newer libwebp releases than the enabled features bind.
+    pub fn has_symbol(&self, symbol: &str) -> bool {
+        let Ok(symbol) = CString::new(symbol) else {
+            return false;
+        };
+        self.handles
+            .iter()
+            .any(|handle| handle.symbol_address(&symbol).is_some())
+    }
+}
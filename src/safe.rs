@@ -0,0 +1,39 @@
+//! Safe, owned wrappers around the raw libwebp interface.
+//!
+//! This module is only available with the `safe` feature. Everything here is
+//! built on top of the raw functions re-exported at the crate root, so both
+//! layers can be mixed freely.
+
+#[cfg(all(feature = "demux", feature = "0_5"))]
+pub use crate::safe::anim_decode::*;
+#[cfg(all(feature = "mux", feature = "0_5"))]
+pub use crate::safe::anim_encode::*;
+pub use crate::safe::decode::*;
+#[cfg(feature = "demux")]
+pub use crate::safe::demux::*;
+pub use crate::safe::encode::*;
+pub use crate::safe::error::*;
+pub use crate::safe::incremental::*;
+#[cfg(feature = "mux")]
+pub use crate::safe::mux::*;
+#[cfg(any(feature = "mux", feature = "demux"))]
+pub use crate::safe::mux_types::*;
+pub use crate::safe::picture::*;
+
+#[cfg(all(feature = "demux", feature = "0_5"))]
+mod anim_decode;
+#[cfg(all(feature = "mux", feature = "0_5"))]
+mod anim_encode;
+mod decode;
+#[cfg(feature = "demux")]
+mod demux;
+mod encode;
+mod error;
+mod incremental;
+#[cfg(any(feature = "mux", feature = "demux"))]
+pub mod metadata;
+#[cfg(feature = "mux")]
+mod mux;
+#[cfg(any(feature = "mux", feature = "demux"))]
+mod mux_types;
+mod picture;
@@ -0,0 +1,245 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::*;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use crate::demux::*;
+use crate::mux_types::*;
+use crate::safe::decode::ColorMode;
+use crate::safe::error::*;
+
+/// Options for [`AnimDecoder`], wrapping [`WebPAnimDecoderOptions`].
+#[derive(Debug, Clone, Default)]
+pub struct AnimDecoderOptions {
+    color_mode: ColorMode,
+    use_threads: bool,
+}
+
+impl AnimDecoderOptions {
+    /// Creates options with RGBA output and no threads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the output colorspace. Only [`ColorMode::Rgba`],
+    /// [`ColorMode::Bgra`], [`ColorMode::RgbaPremultiplied`] and
+    /// [`ColorMode::BgraPremultiplied`] are supported.
+    pub fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.color_mode = color_mode;
+        self
+    }
+
+    /// Uses multi-threaded decoding if libwebp was built with thread support.
+    pub fn use_threads(mut self, use_threads: bool) -> Self {
+        self.use_threads = use_threads;
+        self
+    }
+
+    fn to_raw(&self) -> Result<WebPAnimDecoderOptions> {
+        match self.color_mode {
+            ColorMode::Rgba
+            | ColorMode::Bgra
+            | ColorMode::RgbaPremultiplied
+            | ColorMode::BgraPremultiplied => {}
+            _ => return Err(DecodeError::InvalidParam.into()),
+        }
+        ensure_runtime_compat()?;
+        let mut options: WebPAnimDecoderOptions = unsafe { mem::zeroed() };
+        if unsafe { WebPAnimDecoderOptionsInit(&mut options) } == 0 {
+            return Err(Error::VersionMismatch);
+        }
+        options.color_mode = self.color_mode.to_raw();
+        options.use_threads = self.use_threads as c_int;
+        Ok(options)
+    }
+}
+
+/// Global information about an animation, mirroring [`WebPAnimInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct AnimInfo {
+    /// Canvas width in pixels.
+    pub canvas_width: u32,
+    /// Canvas height in pixels.
+    pub canvas_height: u32,
+    /// Number of times to play the animation; 0 means infinitely.
+    pub loop_count: u32,
+    /// Background color in `[Blue, Green, Red, Alpha]` byte order.
+    pub bgcolor: u32,
+    /// Number of frames.
+    pub frame_count: u32,
+}
+
+impl From<WebPAnimInfo> for AnimInfo {
+    fn from(info: WebPAnimInfo) -> Self {
+        AnimInfo {
+            canvas_width: info.canvas_width,
+            canvas_height: info.canvas_height,
+            loop_count: info.loop_count,
+            bgcolor: info.bgcolor,
+            frame_count: info.frame_count,
+        }
+    }
+}
+
+/// A decoder for animated WebP images owning a [`WebPAnimDecoder`].
+///
+/// The decoder borrows the input for `'a` and yields fully reconstructed
+/// canvases, one per frame.
+///
+/// ```no_run
+/// use libwebp_sys::safe::AnimDecoder;
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// # let data: &[u8] = &[];
+/// let decoder = AnimDecoder::new(data)?;
+/// let info = *decoder.info();
+/// for frame in decoder {
+///     let frame = frame?;
+///     let len = info.canvas_width as usize * info.canvas_height as usize * 4;
+///     assert_eq!(frame.pixels().len(), len);
+///     println!("frame at {} ms", frame.timestamp_ms());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AnimDecoder<'a> {
+    dec: NonNull<WebPAnimDecoder>,
+    info: AnimInfo,
+    color_mode: ColorMode,
+    failed: bool,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+// The decoder has no thread affinity and is only used through `&mut self`.
+unsafe impl Send for AnimDecoder<'_> {}
+
+impl<'a> AnimDecoder<'a> {
+    /// Creates a decoder with RGBA output.
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        Self::with_options(data, &AnimDecoderOptions::new())
+    }
+
+    /// Creates a decoder with the given options.
+    pub fn with_options(data: &'a [u8], options: &AnimDecoderOptions) -> Result<Self> {
+        let raw_options = options.to_raw()?;
+        let data = WebPData {
+            bytes: data.as_ptr(),
+            size: data.len(),
+        };
+        let dec = unsafe { WebPAnimDecoderNew(&data, &raw_options) };
+        // libwebp does not say why it failed; invalid input is the usual cause.
+        let dec = NonNull::new(dec).ok_or(DecodeError::BitstreamError)?;
+        let mut info: WebPAnimInfo = unsafe { mem::zeroed() };
+        let ok = unsafe { WebPAnimDecoderGetInfo(dec.as_ptr(), &mut info) } != 0;
+        let decoder = AnimDecoder {
+            dec,
+            info: info.into(),
+            color_mode: options.color_mode,
+            failed: false,
+            _marker: PhantomData,
+        };
+        if !ok {
+            return Err(DecodeError::InvalidParam.into());
+        }
+        Ok(decoder)
+    }
+
+    /// Global information about the animation.
+    pub fn info(&self) -> &AnimInfo {
+        &self.info
+    }
+
+    /// True if there are frames left to decode.
+    pub fn has_more_frames(&self) -> bool {
+        !self.failed && unsafe { WebPAnimDecoderHasMoreFrames(self.dec.as_ptr()) } != 0
+    }
+
+    /// Rewinds to the first frame, e.g. to play the animation again. The
+    /// iterator yields frames again even if it had already returned `None`.
+    pub fn reset(&mut self) {
+        unsafe { WebPAnimDecoderReset(self.dec.as_ptr()) };
+        self.failed = false;
+    }
+
+    fn canvas_len(&self) -> usize {
+        self.info.canvas_width as usize * self.info.canvas_height as usize * 4
+    }
+}
+
+impl Iterator for AnimDecoder<'_> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.has_more_frames() {
+            return None;
+        }
+        let mut buf: *mut u8 = ptr::null_mut();
+        let mut timestamp: c_int = 0;
+        if unsafe { WebPAnimDecoderGetNext(self.dec.as_ptr(), &mut buf, &mut timestamp) } == 0 {
+            // Further calls would keep failing on the same frame.
+            self.failed = true;
+            return Some(Err(DecodeError::BitstreamError.into()));
+        }
+        // The canvas is reused for the next frame, so it has to be copied.
+        let pixels = unsafe { slice::from_raw_parts(buf, self.canvas_len()) }.to_vec();
+        Some(Ok(Frame {
+            pixels,
+            width: self.info.canvas_width,
+            height: self.info.canvas_height,
+            color_mode: self.color_mode,
+            timestamp_ms: timestamp,
+        }))
+    }
+}
+
+impl Drop for AnimDecoder<'_> {
+    fn drop(&mut self) {
+        unsafe { WebPAnimDecoderDelete(self.dec.as_ptr()) };
+    }
+}
+
+/// A fully reconstructed canvas returned by [`AnimDecoder`].
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    color_mode: ColorMode,
+    timestamp_ms: c_int,
+}
+
+impl Frame {
+    /// Canvas width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Canvas height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The colorspace of the samples.
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// The timestamp of this frame in milliseconds. libwebp reports the time
+    /// at which the frame ends, i.e. the sum of all durations so far.
+    pub fn timestamp_ms(&self) -> i32 {
+        self.timestamp_ms
+    }
+
+    /// The canvas samples, 4 bytes per pixel without padding between rows.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Takes the canvas samples.
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+}
@@ -0,0 +1,205 @@
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::*;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use crate::mux::*;
+use crate::mux_types::*;
+use crate::safe::encode::{Config, PixelLayout};
+use crate::safe::error::*;
+use crate::safe::picture::Picture;
+
+/// Options for [`AnimEncoder`], wrapping [`WebPAnimEncoderOptions`].
+#[derive(Debug, Clone)]
+pub struct AnimEncoderOptions {
+    minimize_size: bool,
+    keyframe_interval: Option<(u32, u32)>,
+    allow_mixed: bool,
+    loop_count: u32,
+    bgcolor: u32,
+}
+
+impl Default for AnimEncoderOptions {
+    fn default() -> Self {
+        AnimEncoderOptions {
+            minimize_size: false,
+            keyframe_interval: None,
+            allow_mixed: false,
+            loop_count: 0,
+            bgcolor: 0xffffffff,
+        }
+    }
+}
+
+impl AnimEncoderOptions {
+    /// Creates options with libwebp's defaults: an infinitely looping
+    /// animation on a white background.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Minimizes the output size at the cost of a much slower encoding.
+    /// Key-frame insertion is disabled in this mode.
+    pub fn minimize_size(mut self, minimize_size: bool) -> Self {
+        self.minimize_size = minimize_size;
+        self
+    }
+
+    /// Sets the minimum and maximum distance between consecutive key-frames.
+    /// By default libwebp picks them based on `minimize_size`.
+    pub fn keyframe_interval(mut self, kmin: u32, kmax: u32) -> Self {
+        self.keyframe_interval = Some((kmin, kmax));
+        self
+    }
+
+    /// Allows mixing lossy and lossless frames, choosing the smaller one for
+    /// each frame.
+    pub fn allow_mixed(mut self, allow_mixed: bool) -> Self {
+        self.allow_mixed = allow_mixed;
+        self
+    }
+
+    /// Sets the number of times to play the animation; 0 means infinitely.
+    pub fn loop_count(mut self, loop_count: u32) -> Self {
+        self.loop_count = loop_count;
+        self
+    }
+
+    /// Sets the background color in `[Blue, Green, Red, Alpha]` byte order.
+    pub fn bgcolor(mut self, bgcolor: u32) -> Self {
+        self.bgcolor = bgcolor;
+        self
+    }
+
+    fn to_raw(&self) -> Result<WebPAnimEncoderOptions> {
+        ensure_runtime_compat()?;
+        let mut options: WebPAnimEncoderOptions = unsafe { mem::zeroed() };
+        if unsafe { WebPAnimEncoderOptionsInit(&mut options) } == 0 {
+            return Err(Error::VersionMismatch);
+        }
+        let to_c_int =
+            |value: u32| c_int::try_from(value).map_err(|_| EncodeError::InvalidConfiguration);
+        options.anim_params.bgcolor = self.bgcolor;
+        options.anim_params.loop_count = to_c_int(self.loop_count)?;
+        options.minimize_size = self.minimize_size as c_int;
+        if let Some((kmin, kmax)) = self.keyframe_interval {
+            options.kmin = to_c_int(kmin)?;
+            options.kmax = to_c_int(kmax)?;
+        }
+        options.allow_mixed = self.allow_mixed as c_int;
+        Ok(options)
+    }
+}
+
+/// An encoder for animated WebP images owning a [`WebPAnimEncoder`].
+///
+/// ```no_run
+/// use libwebp_sys::safe::{AnimEncoder, AnimEncoderOptions, Config};
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// # let frames: Vec<Vec<u8>> = vec![];
+/// let mut encoder = AnimEncoder::new(64, 64, &AnimEncoderOptions::new())?;
+/// let config = Config::new_lossless();
+/// let mut timestamp_ms = 0;
+/// for rgba in &frames {
+///     encoder.add_rgba(rgba, timestamp_ms, &config)?;
+///     timestamp_ms += 100;
+/// }
+/// let webp = encoder.finish(timestamp_ms)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AnimEncoder {
+    enc: NonNull<WebPAnimEncoder>,
+    width: u32,
+    height: u32,
+}
+
+// The encoder has no thread affinity and is only used through `&mut self`.
+unsafe impl Send for AnimEncoder {}
+
+impl AnimEncoder {
+    /// Creates an encoder for a canvas of the given size.
+    pub fn new(width: u32, height: u32, options: &AnimEncoderOptions) -> Result<Self> {
+        let raw_options = options.to_raw()?;
+        let c_width = c_int::try_from(width).map_err(|_| EncodeError::BadDimension)?;
+        let c_height = c_int::try_from(height).map_err(|_| EncodeError::BadDimension)?;
+        let enc = unsafe { WebPAnimEncoderNew(c_width, c_height, &raw_options) };
+        // Fails on invalid dimensions, or when out of memory.
+        let enc = NonNull::new(enc).ok_or(EncodeError::BadDimension)?;
+        Ok(AnimEncoder { enc, width, height })
+    }
+
+    /// Canvas width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Canvas height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn error(&self) -> AnimEncodeError {
+        let message = unsafe { WebPAnimEncoderGetError(self.enc.as_ptr()) };
+        let message = if message.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        AnimEncodeError::new(message)
+    }
+
+    /// Adds a canvas-sized frame shown from `timestamp_ms` on. Timestamps
+    /// must be non-decreasing.
+    pub fn add_frame(
+        &mut self,
+        picture: &mut Picture<'_>,
+        timestamp_ms: i32,
+        config: &Config,
+    ) -> Result<()> {
+        let config = config.to_raw()?;
+        let raw = unsafe { picture.as_raw_mut() };
+        if unsafe { WebPAnimEncoderAdd(self.enc.as_ptr(), raw, timestamp_ms, &config) } == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// Adds a frame from tightly packed RGBA rows covering the whole canvas.
+    pub fn add_rgba(&mut self, rgba: &[u8], timestamp_ms: i32, config: &Config) -> Result<()> {
+        let mut picture = Picture::from_pixels(rgba, PixelLayout::Rgba, self.width, self.height)?;
+        self.add_frame(&mut picture, timestamp_ms, config)
+    }
+
+    /// Ends the animation at `end_timestamp_ms`, which sets the duration of
+    /// the last frame, and returns the assembled WebP file.
+    pub fn finish(self, end_timestamp_ms: i32) -> Result<Vec<u8>> {
+        let enc = self.enc.as_ptr();
+        let ok = unsafe { WebPAnimEncoderAdd(enc, ptr::null_mut(), end_timestamp_ms, ptr::null()) };
+        if ok == 0 {
+            return Err(self.error().into());
+        }
+        let mut data: WebPData = unsafe { mem::zeroed() };
+        if unsafe { WebPAnimEncoderAssemble(enc, &mut data) } == 0 {
+            return Err(self.error().into());
+        }
+        let output = if data.size == 0 {
+            Vec::new()
+        } else {
+            unsafe { slice::from_raw_parts(data.bytes, data.size) }.to_vec()
+        };
+        unsafe { WebPDataClear(&mut data) };
+        Ok(output)
+    }
+}
+
+impl Drop for AnimEncoder {
+    fn drop(&mut self) {
+        unsafe { WebPAnimEncoderDelete(self.enc.as_ptr()) };
+    }
+}
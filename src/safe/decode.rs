@@ -0,0 +1,456 @@
+use std::mem;
+use std::os::raw::*;
+use std::slice;
+
+use crate::decode::*;
+use crate::safe::error::*;
+
+/// Output colorspace, mirroring [`WEBP_CSP_MODE`].
+///
+/// Lowercase letters in the C names (e.g. `MODE_rgbA`) denote premultiplied
+/// RGB channels; here they are spelled out as `*Premultiplied`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum ColorMode {
+    /// `MODE_RGB`
+    Rgb,
+    /// `MODE_RGBA`
+    #[default]
+    Rgba,
+    /// `MODE_BGR`
+    Bgr,
+    /// `MODE_BGRA`
+    Bgra,
+    /// `MODE_ARGB`
+    Argb,
+    /// `MODE_RGBA_4444`
+    Rgba4444,
+    /// `MODE_RGB_565`
+    Rgb565,
+    /// `MODE_rgbA`
+    RgbaPremultiplied,
+    /// `MODE_bgrA`
+    BgraPremultiplied,
+    /// `MODE_Argb`
+    ArgbPremultiplied,
+    /// `MODE_rgbA_4444`
+    Rgba4444Premultiplied,
+    /// `MODE_YUV`
+    Yuv,
+    /// `MODE_YUVA`
+    Yuva,
+}
+
+impl ColorMode {
+    /// Returns the raw `WEBP_CSP_MODE` value.
+    pub fn to_raw(self) -> WEBP_CSP_MODE {
+        match self {
+            ColorMode::Rgb => MODE_RGB,
+            ColorMode::Rgba => MODE_RGBA,
+            ColorMode::Bgr => MODE_BGR,
+            ColorMode::Bgra => MODE_BGRA,
+            ColorMode::Argb => MODE_ARGB,
+            ColorMode::Rgba4444 => MODE_RGBA_4444,
+            ColorMode::Rgb565 => MODE_RGB_565,
+            ColorMode::RgbaPremultiplied => MODE_rgbA,
+            ColorMode::BgraPremultiplied => MODE_bgrA,
+            ColorMode::ArgbPremultiplied => MODE_Argb,
+            ColorMode::Rgba4444Premultiplied => MODE_rgbA_4444,
+            ColorMode::Yuv => MODE_YUV,
+            ColorMode::Yuva => MODE_YUVA,
+        }
+    }
+
+    /// Converts a raw `WEBP_CSP_MODE` value. Returns `None` for `MODE_LAST`
+    /// and unknown values.
+    #[allow(non_upper_case_globals)]
+    pub fn from_raw(mode: WEBP_CSP_MODE) -> Option<Self> {
+        Some(match mode {
+            MODE_RGB => ColorMode::Rgb,
+            MODE_RGBA => ColorMode::Rgba,
+            MODE_BGR => ColorMode::Bgr,
+            MODE_BGRA => ColorMode::Bgra,
+            MODE_ARGB => ColorMode::Argb,
+            MODE_RGBA_4444 => ColorMode::Rgba4444,
+            MODE_RGB_565 => ColorMode::Rgb565,
+            MODE_rgbA => ColorMode::RgbaPremultiplied,
+            MODE_bgrA => ColorMode::BgraPremultiplied,
+            MODE_Argb => ColorMode::ArgbPremultiplied,
+            MODE_rgbA_4444 => ColorMode::Rgba4444Premultiplied,
+            MODE_YUV => ColorMode::Yuv,
+            MODE_YUVA => ColorMode::Yuva,
+            _ => return None,
+        })
+    }
+
+    /// True for the packed RGB modes, false for the planar YUV ones.
+    pub fn is_rgb(self) -> bool {
+        WebPIsRGBMode(self.to_raw()) != 0
+    }
+
+    /// True if the mode carries an alpha channel.
+    pub fn has_alpha(self) -> bool {
+        WebPIsAlphaMode(self.to_raw()) != 0
+    }
+
+    /// True if the RGB channels are premultiplied by alpha.
+    pub fn is_premultiplied(self) -> bool {
+        WebPIsPremultipliedMode(self.to_raw()) != 0
+    }
+
+    /// Number of bytes per pixel for packed RGB modes, or `None` for YUV.
+    pub fn bytes_per_pixel(self) -> Option<usize> {
+        match self {
+            ColorMode::Rgb | ColorMode::Bgr => Some(3),
+            ColorMode::Rgba
+            | ColorMode::Bgra
+            | ColorMode::Argb
+            | ColorMode::RgbaPremultiplied
+            | ColorMode::BgraPremultiplied
+            | ColorMode::ArgbPremultiplied => Some(4),
+            ColorMode::Rgba4444 | ColorMode::Rgb565 | ColorMode::Rgba4444Premultiplied => Some(2),
+            ColorMode::Yuv | ColorMode::Yuva => None,
+        }
+    }
+}
+
+/// Builder for advanced decoding, wrapping [`WebPDecoderConfig`].
+///
+/// ```no_run
+/// use libwebp_sys::safe::{ColorMode, Decoder};
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// # let data: &[u8] = &[];
+/// let image = Decoder::new()
+///     .color_mode(ColorMode::Rgb)
+///     .scale(64, 64)
+///     .decode(data)?;
+/// for row in image.rgb().unwrap().rows() {
+///     assert_eq!(row.len(), 64 * 3);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    color_mode: ColorMode,
+    bypass_filtering: bool,
+    no_fancy_upsampling: bool,
+    crop: Option<(u32, u32, u32, u32)>,
+    scale: Option<(u32, u32)>,
+    use_threads: bool,
+    dithering_strength: u8,
+    #[cfg(feature = "0_5")]
+    alpha_dithering_strength: u8,
+    #[cfg(feature = "0_5")]
+    flip: bool,
+}
+
+impl Decoder {
+    /// Creates a decoder with libwebp's default options and RGBA output.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the output colorspace. Defaults to [`ColorMode::Rgba`].
+    pub fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.color_mode = color_mode;
+        self
+    }
+
+    /// Skips the in-loop filtering.
+    pub fn bypass_filtering(mut self, bypass_filtering: bool) -> Self {
+        self.bypass_filtering = bypass_filtering;
+        self
+    }
+
+    /// Uses the faster pointwise upsampler.
+    pub fn no_fancy_upsampling(mut self, no_fancy_upsampling: bool) -> Self {
+        self.no_fancy_upsampling = no_fancy_upsampling;
+        self
+    }
+
+    /// Crops the image to the given rectangle before scaling.
+    /// `left` and `top` are snapped to even values by libwebp.
+    pub fn crop(mut self, left: u32, top: u32, width: u32, height: u32) -> Self {
+        self.crop = Some((left, top, width, height));
+        self
+    }
+
+    /// Scales the (possibly cropped) image to the given size.
+    pub fn scale(mut self, width: u32, height: u32) -> Self {
+        self.scale = Some((width, height));
+        self
+    }
+
+    /// Uses multi-threaded decoding if libwebp was built with thread support.
+    pub fn use_threads(mut self, use_threads: bool) -> Self {
+        self.use_threads = use_threads;
+        self
+    }
+
+    /// Sets the dithering strength, from 0 (off) to 100 (full).
+    pub fn dithering(mut self, strength: u8) -> Self {
+        self.dithering_strength = strength;
+        self
+    }
+
+    /// Sets the alpha dithering strength, from 0 (off) to 100 (full).
+    #[cfg(feature = "0_5")]
+    pub fn alpha_dithering(mut self, strength: u8) -> Self {
+        self.alpha_dithering_strength = strength;
+        self
+    }
+
+    /// Flips the output vertically.
+    #[cfg(feature = "0_5")]
+    pub fn flip(mut self, flip: bool) -> Self {
+        self.flip = flip;
+        self
+    }
+
+    /// Initializes a raw [`WebPDecoderConfig`] with these options.
+    pub(crate) fn to_config(&self) -> Result<WebPDecoderConfig> {
+        ensure_runtime_compat()?;
+        let mut config: WebPDecoderConfig = unsafe { mem::zeroed() };
+        if unsafe { WebPInitDecoderConfig(&mut config) } == 0 {
+            return Err(Error::VersionMismatch);
+        }
+        config.output.colorspace = self.color_mode.to_raw();
+        let options = &mut config.options;
+        options.bypass_filtering = self.bypass_filtering as c_int;
+        options.no_fancy_upsampling = self.no_fancy_upsampling as c_int;
+        if let Some((left, top, width, height)) = self.crop {
+            options.use_cropping = 1;
+            options.crop_left = to_c_int(left)?;
+            options.crop_top = to_c_int(top)?;
+            options.crop_width = to_c_int(width)?;
+            options.crop_height = to_c_int(height)?;
+        }
+        if let Some((width, height)) = self.scale {
+            options.use_scaling = 1;
+            options.scaled_width = to_c_int(width)?;
+            options.scaled_height = to_c_int(height)?;
+        }
+        options.use_threads = self.use_threads as c_int;
+        options.dithering_strength = self.dithering_strength.into();
+        #[cfg(feature = "0_5")]
+        {
+            options.alpha_dithering_strength = self.alpha_dithering_strength.into();
+            options.flip = self.flip as c_int;
+        }
+        Ok(config)
+    }
+
+    /// Decodes a complete WebP image.
+    pub fn decode(&self, data: &[u8]) -> Result<DecodedImage> {
+        let mut config = self.to_config()?;
+        let status = unsafe { WebPDecode(data.as_ptr(), data.len(), &mut config) };
+        // Take ownership first so that any partially allocated output is freed.
+        let image = unsafe { DecodedImage::from_raw(config.output) };
+        DecodeError::check(status)?;
+        Ok(image)
+    }
+}
+
+pub(crate) fn to_c_int(value: u32) -> Result<c_int> {
+    c_int::try_from(value).map_err(|_| Error::Decode(DecodeError::InvalidParam))
+}
+
+/// A decoded image owning its [`WebPDecBuffer`].
+///
+/// The buffer is released with `WebPFreeDecBuffer` on drop.
+#[derive(Debug)]
+pub struct DecodedImage {
+    buffer: WebPDecBuffer,
+}
+
+// The buffer is uniquely owned and never mutated through shared references.
+unsafe impl Send for DecodedImage {}
+unsafe impl Sync for DecodedImage {}
+
+impl DecodedImage {
+    /// Takes ownership of a buffer filled by libwebp.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must have been passed to a decode into internal memory, and
+    /// must not be freed elsewhere. If the decode failed, the image must be
+    /// dropped without reading its planes.
+    pub(crate) unsafe fn from_raw(buffer: WebPDecBuffer) -> Self {
+        DecodedImage { buffer }
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.buffer.width as u32
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.buffer.height as u32
+    }
+
+    /// The colorspace of the samples.
+    pub fn color_mode(&self) -> ColorMode {
+        ColorMode::from_raw(self.buffer.colorspace).expect("libwebp returned an invalid colorspace")
+    }
+
+    /// The packed samples, or `None` if the image was decoded to YUV.
+    pub fn rgb(&self) -> Option<Plane<'_>> {
+        unsafe { rgb_plane(&self.buffer, self.height()) }
+    }
+
+    /// The sample planes, or `None` if the image was decoded to RGB.
+    pub fn yuva(&self) -> Option<YuvaPlanes<'_>> {
+        unsafe { yuva_planes(&self.buffer, self.height()) }
+    }
+
+    /// The underlying raw buffer.
+    pub fn as_raw(&self) -> &WebPDecBuffer {
+        &self.buffer
+    }
+}
+
+impl Drop for DecodedImage {
+    fn drop(&mut self) {
+        unsafe { WebPFreeDecBuffer(&mut self.buffer) };
+    }
+}
+
+/// Borrows the first `rows` rows of a packed RGB buffer, or returns `None`
+/// if the buffer is flipped (negative stride).
+///
+/// # Safety
+///
+/// `buffer` must describe valid memory holding at least `rows` rows.
+pub(crate) unsafe fn rgb_plane(buffer: &WebPDecBuffer, rows: u32) -> Option<Plane<'_>> {
+    let bytes_per_pixel = ColorMode::from_raw(buffer.colorspace)?.bytes_per_pixel()?;
+    let rgba = unsafe { &buffer.u.RGBA };
+    let stride = usize::try_from(rgba.stride).ok()?;
+    Some(unsafe {
+        Plane::from_raw(
+            rgba.rgba,
+            rgba.size,
+            stride,
+            buffer.width as usize * bytes_per_pixel,
+            rows as usize,
+        )
+    })
+}
+
+/// Borrows the first `rows` luma rows of a YUV(A) buffer, together with the
+/// matching chroma and alpha rows, or returns `None` if the buffer is flipped
+/// (negative strides).
+///
+/// # Safety
+///
+/// `buffer` must describe valid memory holding at least `rows` rows.
+pub(crate) unsafe fn yuva_planes(buffer: &WebPDecBuffer, rows: u32) -> Option<YuvaPlanes<'_>> {
+    if WebPIsRGBMode(buffer.colorspace) != 0 {
+        return None;
+    }
+    let yuva = unsafe { &buffer.u.YUVA };
+    let (width, height) = (buffer.width as usize, rows as usize);
+    let (uv_width, uv_height) = (width.div_ceil(2), height.div_ceil(2));
+    let y_stride = usize::try_from(yuva.y_stride).ok()?;
+    let u_stride = usize::try_from(yuva.u_stride).ok()?;
+    let v_stride = usize::try_from(yuva.v_stride).ok()?;
+    let a = if yuva.a.is_null() {
+        None
+    } else {
+        let a_stride = usize::try_from(yuva.a_stride).ok()?;
+        Some(unsafe { Plane::from_raw(yuva.a, yuva.a_size, a_stride, width, height) })
+    };
+    unsafe {
+        Some(YuvaPlanes {
+            y: Plane::from_raw(yuva.y, yuva.y_size, y_stride, width, height),
+            u: Plane::from_raw(yuva.u, yuva.u_size, u_stride, uv_width, uv_height),
+            v: Plane::from_raw(yuva.v, yuva.v_size, v_stride, uv_width, uv_height),
+            a,
+        })
+    }
+}
+
+/// A borrowed plane of samples laid out row by row.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane<'a> {
+    data: &'a [u8],
+    stride: usize,
+    row_bytes: usize,
+    height: usize,
+}
+
+impl<'a> Plane<'a> {
+    /// # Safety
+    ///
+    /// `data` must be valid for reads of `size` bytes for `'a`, and must hold
+    /// `height` rows of `row_bytes` bytes each, `stride` bytes apart.
+    pub(crate) unsafe fn from_raw(
+        data: *const u8,
+        size: usize,
+        stride: usize,
+        row_bytes: usize,
+        height: usize,
+    ) -> Self {
+        let data = if data.is_null() || size == 0 {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(data, size) }
+        };
+        Plane {
+            data,
+            stride,
+            row_bytes,
+            height,
+        }
+    }
+
+    /// The whole plane, including any padding between rows.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Distance in bytes between the start of two consecutive rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Number of meaningful bytes in each row.
+    pub fn row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the `y`-th row without padding.
+    pub fn row(&self, y: usize) -> Option<&'a [u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let start = y * self.stride;
+        self.data.get(start..start + self.row_bytes)
+    }
+
+    /// Iterates over the rows without padding.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let plane = *self;
+        (0..self.height).map_while(move |y| plane.row(y))
+    }
+}
+
+/// Borrowed planes of a YUV(A) image.
+#[derive(Debug, Clone, Copy)]
+pub struct YuvaPlanes<'a> {
+    /// Luma plane.
+    pub y: Plane<'a>,
+    /// Chroma U plane, subsampled by two in both directions.
+    pub u: Plane<'a>,
+    /// Chroma V plane, subsampled by two in both directions.
+    pub v: Plane<'a>,
+    /// Alpha plane, if the image has one and [`ColorMode::Yuva`] was requested.
+    pub a: Option<Plane<'a>>,
+}
@@ -0,0 +1,290 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::*;
+use std::ptr::NonNull;
+
+use crate::demux::*;
+use crate::mux_types::*;
+use crate::safe::error::*;
+use crate::safe::mux_types::*;
+
+/// A parsed WebP container owning a [`WebPDemuxer`].
+///
+/// The demuxer borrows the input for `'a`, and so do the frames and chunks
+/// it returns, which stay valid after the demuxer is dropped.
+///
+/// ```no_run
+/// use libwebp_sys::safe::Demuxer;
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// # let data: &[u8] = &[];
+/// let demuxer = Demuxer::new(data)?;
+/// for frame in demuxer.frames() {
+///     println!("{}x{} for {} ms", frame.width(), frame.height(), frame.duration());
+/// }
+/// let icc_profile = demuxer.chunks(b"ICCP").next();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Demuxer<'a> {
+    dmux: NonNull<WebPDemuxer>,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+// The demuxer is never modified after parsing.
+unsafe impl Send for Demuxer<'_> {}
+unsafe impl Sync for Demuxer<'_> {}
+
+impl<'a> Demuxer<'a> {
+    /// Parses a complete WebP file.
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        ensure_runtime_compat()?;
+        let data = WebPData {
+            bytes: data.as_ptr(),
+            size: data.len(),
+        };
+        let mut state = WEBP_DEMUX_PARSE_ERROR;
+        let dmux = unsafe { WebPDemuxInternal(&data, 0, &mut state, WEBP_DEMUX_ABI_VERSION) };
+        let dmux = NonNull::new(dmux).ok_or(match state {
+            WEBP_DEMUX_PARSING_HEADER | WEBP_DEMUX_PARSED_HEADER => DecodeError::NotEnoughData,
+            _ => DecodeError::BitstreamError,
+        })?;
+        Ok(Demuxer {
+            dmux,
+            _marker: PhantomData,
+        })
+    }
+
+    fn get(&self, feature: WebPFormatFeature) -> u32 {
+        unsafe { WebPDemuxGetI(self.dmux.as_ptr(), feature) }
+    }
+
+    /// The `*_FLAG` bits of the VP8X chunk (`WEBP_FF_FORMAT_FLAGS`).
+    pub fn format_flags(&self) -> WebPFeatureFlags {
+        self.get(WEBP_FF_FORMAT_FLAGS)
+    }
+
+    /// Canvas width in pixels (`WEBP_FF_CANVAS_WIDTH`).
+    pub fn canvas_width(&self) -> u32 {
+        self.get(WEBP_FF_CANVAS_WIDTH)
+    }
+
+    /// Canvas height in pixels (`WEBP_FF_CANVAS_HEIGHT`).
+    pub fn canvas_height(&self) -> u32 {
+        self.get(WEBP_FF_CANVAS_HEIGHT)
+    }
+
+    /// Number of times to play the animation; 0 means infinitely
+    /// (`WEBP_FF_LOOP_COUNT`).
+    pub fn loop_count(&self) -> u32 {
+        self.get(WEBP_FF_LOOP_COUNT)
+    }
+
+    /// Background color in `[Blue, Green, Red, Alpha]` byte order
+    /// (`WEBP_FF_BACKGROUND_COLOR`).
+    pub fn background_color(&self) -> u32 {
+        self.get(WEBP_FF_BACKGROUND_COLOR)
+    }
+
+    /// Number of frames; 1 for still images (`WEBP_FF_FRAME_COUNT`).
+    pub fn frame_count(&self) -> u32 {
+        self.get(WEBP_FF_FRAME_COUNT)
+    }
+
+    /// Returns the frame with the given 1-based index. Index 0 returns the
+    /// last frame.
+    pub fn frame(&self, frame_number: u32) -> Option<DemuxFrame<'a>> {
+        let frame_number = c_int::try_from(frame_number).ok()?;
+        let mut iter: WebPIterator = unsafe { mem::zeroed() };
+        if unsafe { WebPDemuxGetFrame(self.dmux.as_ptr(), frame_number, &mut iter) } == 0 {
+            return None;
+        }
+        let frame = unsafe { DemuxFrame::from_raw(&iter) };
+        unsafe { WebPDemuxReleaseIterator(&mut iter) };
+        Some(frame)
+    }
+
+    /// Iterates over all frames in order.
+    pub fn frames(&self) -> Frames<'_, 'a> {
+        let mut iter: WebPIterator = unsafe { mem::zeroed() };
+        let valid = unsafe { WebPDemuxGetFrame(self.dmux.as_ptr(), 1, &mut iter) } != 0;
+        Frames {
+            iter,
+            valid,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Iterates over the payloads of all chunks with the given FourCC, e.g.
+    /// `b"EXIF"`, `b"ICCP"` or `b"XMP "`.
+    pub fn chunks(&self, fourcc: &[u8; 4]) -> Chunks<'_, 'a> {
+        let mut iter: WebPChunkIterator = unsafe { mem::zeroed() };
+        let fourcc = fourcc.as_ptr() as *const c_char;
+        let valid = unsafe { WebPDemuxGetChunk(self.dmux.as_ptr(), fourcc, 1, &mut iter) } != 0;
+        Chunks {
+            iter,
+            valid,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for Demuxer<'_> {
+    fn drop(&mut self) {
+        unsafe { WebPDemuxDelete(self.dmux.as_ptr()) };
+    }
+}
+
+/// A frame returned by [`Demuxer`], mirroring [`WebPIterator`].
+#[derive(Debug, Clone, Copy)]
+pub struct DemuxFrame<'a> {
+    frame_number: u32,
+    x_offset: u32,
+    y_offset: u32,
+    width: u32,
+    height: u32,
+    duration: u32,
+    dispose_method: DisposeMethod,
+    blend_method: BlendMethod,
+    has_alpha: bool,
+    complete: bool,
+    data: &'a [u8],
+}
+
+impl<'a> DemuxFrame<'a> {
+    /// # Safety
+    ///
+    /// `iter` must have been filled by libwebp from data borrowed for `'a`.
+    unsafe fn from_raw(iter: &WebPIterator) -> Self {
+        DemuxFrame {
+            frame_number: iter.frame_num as u32,
+            x_offset: iter.x_offset as u32,
+            y_offset: iter.y_offset as u32,
+            width: iter.width as u32,
+            height: iter.height as u32,
+            duration: iter.duration as u32,
+            dispose_method: DisposeMethod::from_raw(iter.dispose_method).unwrap_or_default(),
+            blend_method: BlendMethod::from_raw(iter.blend_method).unwrap_or_default(),
+            has_alpha: iter.has_alpha != 0,
+            complete: iter.complete != 0,
+            data: unsafe { data_slice(&iter.fragment) },
+        }
+    }
+
+    /// The 1-based index of the frame.
+    pub fn frame_number(&self) -> u32 {
+        self.frame_number
+    }
+
+    /// Horizontal offset of the frame on the canvas.
+    pub fn x_offset(&self) -> u32 {
+        self.x_offset
+    }
+
+    /// Vertical offset of the frame on the canvas.
+    pub fn y_offset(&self) -> u32 {
+        self.y_offset
+    }
+
+    /// Width of the frame in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the frame in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Display duration in milliseconds.
+    pub fn duration(&self) -> u32 {
+        self.duration
+    }
+
+    /// How the frame is treated after it has been displayed.
+    pub fn dispose_method(&self) -> DisposeMethod {
+        self.dispose_method
+    }
+
+    /// How the frame is combined with the canvas.
+    pub fn blend_method(&self) -> BlendMethod {
+        self.blend_method
+    }
+
+    /// True if the frame carries an alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        self.has_alpha
+    }
+
+    /// False if the frame's data is truncated.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The frame's bitstream (the `ALPH` and `VP8 ` or `VP8L` chunks), which
+    /// can be passed to the still-image decoder.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// An iterator over the frames of a [`Demuxer`], wrapping [`WebPIterator`].
+#[derive(Debug)]
+pub struct Frames<'d, 'a> {
+    iter: WebPIterator,
+    valid: bool,
+    _marker: PhantomData<&'d Demuxer<'a>>,
+}
+
+impl<'a> Iterator for Frames<'_, 'a> {
+    type Item = DemuxFrame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.valid {
+            return None;
+        }
+        let frame = unsafe { DemuxFrame::from_raw(&self.iter) };
+        self.valid = unsafe { WebPDemuxNextFrame(&mut self.iter) } != 0;
+        Some(frame)
+    }
+}
+
+impl FusedIterator for Frames<'_, '_> {}
+
+impl Drop for Frames<'_, '_> {
+    fn drop(&mut self) {
+        unsafe { WebPDemuxReleaseIterator(&mut self.iter) };
+    }
+}
+
+/// An iterator over the payloads of the chunks of a [`Demuxer`] with a given
+/// FourCC, wrapping [`WebPChunkIterator`].
+#[derive(Debug)]
+pub struct Chunks<'d, 'a> {
+    iter: WebPChunkIterator,
+    valid: bool,
+    _marker: PhantomData<&'d Demuxer<'a>>,
+}
+
+impl<'a> Iterator for Chunks<'_, 'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.valid {
+            return None;
+        }
+        let chunk = unsafe { data_slice(&self.iter.chunk) };
+        self.valid = unsafe { WebPDemuxNextChunk(&mut self.iter) } != 0;
+        Some(chunk)
+    }
+}
+
+impl FusedIterator for Chunks<'_, '_> {}
+
+impl Drop for Chunks<'_, '_> {
+    fn drop(&mut self) {
+        unsafe { WebPDemuxReleaseChunkIterator(&mut self.iter) };
+    }
+}
@@ -0,0 +1,462 @@
+use std::any::Any;
+use std::io::{self, Write};
+use std::mem;
+use std::os::raw::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::encode::*;
+use crate::safe::error::*;
+use crate::safe::picture::Picture;
+
+/// Predefined settings, mirroring [`WebPPreset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Preset {
+    /// default preset.
+    #[default]
+    Default,
+    /// digital picture, like portrait, inner shot
+    Picture,
+    /// outdoor photograph, with natural lighting
+    Photo,
+    /// hand or line drawing, with high-contrast details
+    Drawing,
+    /// small-sized colorful images
+    Icon,
+    /// text-like
+    Text,
+}
+
+impl Preset {
+    /// Returns the raw `WebPPreset` value.
+    pub fn to_raw(self) -> WebPPreset {
+        match self {
+            Preset::Default => WEBP_PRESET_DEFAULT,
+            Preset::Picture => WEBP_PRESET_PICTURE,
+            Preset::Photo => WEBP_PRESET_PHOTO,
+            Preset::Drawing => WEBP_PRESET_DRAWING,
+            Preset::Icon => WEBP_PRESET_ICON,
+            Preset::Text => WEBP_PRESET_TEXT,
+        }
+    }
+}
+
+/// Image characteristics hint, mirroring [`WebPImageHint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum ImageHint {
+    /// default preset.
+    #[default]
+    Default,
+    /// digital picture, like portrait, inner shot
+    Picture,
+    /// outdoor photograph, with natural lighting
+    Photo,
+    /// Discrete tone image (graph, map-tile etc).
+    Graph,
+}
+
+impl ImageHint {
+    /// Returns the raw `WebPImageHint` value.
+    pub fn to_raw(self) -> WebPImageHint {
+        match self {
+            ImageHint::Default => WEBP_HINT_DEFAULT,
+            ImageHint::Picture => WEBP_HINT_PICTURE,
+            ImageHint::Photo => WEBP_HINT_PHOTO,
+            ImageHint::Graph => WEBP_HINT_GRAPH,
+        }
+    }
+}
+
+/// Compression parameters, wrapping [`WebPConfig`].
+///
+/// The defaults are those of `WebPConfigInit()`: lossy, quality 75, method 4.
+#[derive(Debug, Clone)]
+pub struct Config {
+    preset: Preset,
+    quality: f32,
+    lossless: bool,
+    method: u8,
+    image_hint: ImageHint,
+    target_size: u32,
+    alpha_quality: u8,
+    multi_threaded: bool,
+    #[cfg(feature = "0_5")]
+    near_lossless: u8,
+    #[cfg(feature = "0_5")]
+    exact: bool,
+    #[cfg(feature = "0_6")]
+    sharp_yuv: bool,
+    #[cfg(feature = "1_2")]
+    qmin: u8,
+    #[cfg(feature = "1_2")]
+    qmax: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            preset: Preset::Default,
+            quality: 75.0,
+            lossless: false,
+            method: 4,
+            image_hint: ImageHint::Default,
+            target_size: 0,
+            alpha_quality: 100,
+            multi_threaded: false,
+            #[cfg(feature = "0_5")]
+            near_lossless: 100,
+            #[cfg(feature = "0_5")]
+            exact: false,
+            #[cfg(feature = "0_6")]
+            sharp_yuv: false,
+            #[cfg(feature = "1_2")]
+            qmin: 0,
+            #[cfg(feature = "1_2")]
+            qmax: 100,
+        }
+    }
+}
+
+impl Config {
+    /// Creates a lossy configuration with libwebp's defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a lossless configuration.
+    pub fn new_lossless() -> Self {
+        Self::default().lossless(true)
+    }
+
+    /// Starts from a predefined set of parameters (see `WebPConfigPreset()`).
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Selects lossless (true) or lossy (false) encoding.
+    pub fn lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Between 0 and 100. For lossy, 0 gives the smallest size and 100 the
+    /// largest. For lossless, this is the amount of effort put into the
+    /// compression.
+    pub fn quality(mut self, quality: f32) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Quality/speed trade-off (0=fast, 6=slower-better).
+    pub fn method(mut self, method: u8) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Hint for image type (lossless only for now).
+    pub fn image_hint(mut self, image_hint: ImageHint) -> Self {
+        self.image_hint = image_hint;
+        self
+    }
+
+    /// If non-zero, sets the desired target size in bytes.
+    pub fn target_size(mut self, target_size: u32) -> Self {
+        self.target_size = target_size;
+        self
+    }
+
+    /// Between 0 (smallest size) and 100 (lossless). Default is 100.
+    pub fn alpha_quality(mut self, alpha_quality: u8) -> Self {
+        self.alpha_quality = alpha_quality;
+        self
+    }
+
+    /// Tries to use multi-threaded encoding.
+    pub fn multi_threaded(mut self, multi_threaded: bool) -> Self {
+        self.multi_threaded = multi_threaded;
+        self
+    }
+
+    /// Near lossless encoding \[0 = max loss .. 100 = off (default)\].
+    #[cfg(feature = "0_5")]
+    pub fn near_lossless(mut self, near_lossless: u8) -> Self {
+        self.near_lossless = near_lossless;
+        self
+    }
+
+    /// Preserves the exact RGB values under transparent area.
+    #[cfg(feature = "0_5")]
+    pub fn exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Uses sharp (and slow) RGB->YUV conversion.
+    #[cfg(feature = "0_6")]
+    pub fn sharp_yuv(mut self, sharp_yuv: bool) -> Self {
+        self.sharp_yuv = sharp_yuv;
+        self
+    }
+
+    /// Minimum permissible quality factor.
+    #[cfg(feature = "1_2")]
+    pub fn qmin(mut self, qmin: u8) -> Self {
+        self.qmin = qmin;
+        self
+    }
+
+    /// Maximum permissible quality factor.
+    #[cfg(feature = "1_2")]
+    pub fn qmax(mut self, qmax: u8) -> Self {
+        self.qmax = qmax;
+        self
+    }
+
+    /// Builds and validates the raw [`WebPConfig`].
+    pub fn to_raw(&self) -> Result<WebPConfig> {
+        ensure_runtime_compat()?;
+        let mut config: WebPConfig = unsafe { mem::zeroed() };
+        if unsafe { WebPConfigPreset(&mut config, self.preset.to_raw(), self.quality) } == 0 {
+            return Err(Error::VersionMismatch);
+        }
+        config.lossless = self.lossless as c_int;
+        config.method = self.method.into();
+        config.image_hint = self.image_hint.to_raw();
+        config.target_size =
+            c_int::try_from(self.target_size).map_err(|_| EncodeError::InvalidConfiguration)?;
+        config.alpha_quality = self.alpha_quality.into();
+        config.thread_level = self.multi_threaded as c_int;
+        #[cfg(feature = "0_5")]
+        {
+            config.near_lossless = self.near_lossless.into();
+            config.exact = self.exact as c_int;
+        }
+        #[cfg(feature = "0_6")]
+        {
+            config.use_sharp_yuv = self.sharp_yuv as c_int;
+        }
+        #[cfg(feature = "1_2")]
+        {
+            config.qmin = self.qmin.into();
+            config.qmax = self.qmax.into();
+        }
+        if unsafe { WebPValidateConfig(&config) } == 0 {
+            return Err(EncodeError::InvalidConfiguration.into());
+        }
+        Ok(config)
+    }
+}
+
+/// Byte order of packed input samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PixelLayout {
+    /// R, G, B
+    Rgb,
+    /// R, G, B, A
+    Rgba,
+    /// R, G, B and an ignored byte
+    Rgbx,
+    /// B, G, R
+    Bgr,
+    /// B, G, R, A
+    Bgra,
+    /// B, G, R and an ignored byte
+    Bgrx,
+}
+
+impl PixelLayout {
+    /// Number of bytes per pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelLayout::Rgb | PixelLayout::Bgr => 3,
+            PixelLayout::Rgba | PixelLayout::Rgbx | PixelLayout::Bgra | PixelLayout::Bgrx => 4,
+        }
+    }
+
+    pub(crate) fn import_fn(
+        self,
+    ) -> unsafe extern "C" fn(*mut WebPPicture, *const u8, c_int) -> c_int {
+        match self {
+            PixelLayout::Rgb => WebPPictureImportRGB,
+            PixelLayout::Rgba => WebPPictureImportRGBA,
+            PixelLayout::Rgbx => WebPPictureImportRGBX,
+            PixelLayout::Bgr => WebPPictureImportBGR,
+            PixelLayout::Bgra => WebPPictureImportBGRA,
+            PixelLayout::Bgrx => WebPPictureImportBGRX,
+        }
+    }
+
+    /// Checks that `len` bytes hold `height` rows `stride` bytes apart.
+    pub(crate) fn check_len(
+        self,
+        len: usize,
+        width: u32,
+        height: u32,
+        stride: usize,
+    ) -> Result<()> {
+        let row_bytes = (width as usize).checked_mul(self.bytes_per_pixel());
+        let expected = match height.checked_sub(1) {
+            None => Some(0),
+            Some(rows) => (rows as usize)
+                .checked_mul(stride)
+                .and_then(|n| n.checked_add(row_bytes?)),
+        };
+        match expected {
+            Some(expected) if expected <= len => Ok(()),
+            expected => Err(Error::BufferTooSmall {
+                expected: expected.unwrap_or(usize::MAX),
+                actual: len,
+            }),
+        }
+    }
+}
+
+/// Encodes borrowed pixel data into WebP bytes.
+///
+/// ```no_run
+/// use libwebp_sys::safe::{Config, Encoder, PixelLayout};
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// let rgba = vec![255; 16 * 16 * 4];
+/// let webp = Encoder::new(Config::new().quality(90.0))
+///     .encode(&rgba, PixelLayout::Rgba, 16, 16)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Encoder {
+    config: Config,
+}
+
+impl Encoder {
+    /// Creates an encoder using the given configuration.
+    pub fn new(config: Config) -> Self {
+        Encoder { config }
+    }
+
+    /// The configuration used by this encoder.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Encodes tightly packed rows of pixels.
+    pub fn encode(
+        &self,
+        pixels: &[u8],
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>> {
+        let stride = width as usize * layout.bytes_per_pixel();
+        self.encode_with_stride(pixels, layout, width, height, stride)
+    }
+
+    /// Encodes rows of pixels that start `stride` bytes apart.
+    pub fn encode_with_stride(
+        &self,
+        pixels: &[u8],
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        stride: usize,
+    ) -> Result<Vec<u8>> {
+        let mut picture = Picture::from_pixels_with_stride(pixels, layout, width, height, stride)?;
+        self.encode_picture(&mut picture)
+    }
+
+    /// Encodes a picture. Depending on the configuration, libwebp may convert
+    /// the picture's samples in place.
+    pub fn encode_picture(&self, picture: &mut Picture<'_>) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.encode_picture_to_writer(picture, &mut output)?;
+        Ok(output)
+    }
+
+    /// Encodes tightly packed rows of pixels, streaming the output to `writer`.
+    pub fn encode_to_writer<W: Write>(
+        &self,
+        pixels: &[u8],
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        writer: W,
+    ) -> Result<()> {
+        let mut picture = Picture::from_pixels(pixels, layout, width, height)?;
+        self.encode_picture_to_writer(&mut picture, writer)
+    }
+
+    /// Encodes a picture, streaming the output to `writer` as libwebp
+    /// produces it.
+    ///
+    /// If `writer` fails, encoding is aborted with `VP8_ENC_ERROR_BAD_WRITE`
+    /// and the original I/O error is returned as [`Error::Io`]. Bytes written
+    /// before the failure are not rolled back.
+    pub fn encode_picture_to_writer<W: Write>(
+        &self,
+        picture: &mut Picture<'_>,
+        writer: W,
+    ) -> Result<()> {
+        let config = self.config.to_raw()?;
+        let mut sink = WriterSink {
+            writer,
+            error: None,
+            panic: None,
+        };
+        let raw = unsafe { picture.as_raw_mut() };
+        raw.writer = Some(write_to_sink::<W>);
+        raw.custom_ptr = &mut sink as *mut WriterSink<W> as *mut c_void;
+        let ok = unsafe { WebPEncode(&config, raw) } != 0;
+        raw.writer = None;
+        raw.custom_ptr = ptr::null_mut();
+        if let Some(payload) = sink.panic {
+            panic::resume_unwind(payload);
+        }
+        if let Some(e) = sink.error {
+            return Err(Error::Io(e));
+        }
+        if !ok {
+            return Err(EncodeError::from_picture(raw).into());
+        }
+        Ok(())
+    }
+}
+
+/// State shared with [`write_to_sink`] through `custom_ptr`.
+struct WriterSink<W> {
+    writer: W,
+    error: Option<io::Error>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+/// A [`WebPWriterFunction`] forwarding to the [`WriterSink`] in `custom_ptr`.
+///
+/// Errors and panics are stashed in the sink, since neither may cross the FFI
+/// boundary; returning false makes libwebp fail with `VP8_ENC_ERROR_BAD_WRITE`.
+extern "C" fn write_to_sink<W: Write>(
+    data: *const u8,
+    data_size: usize,
+    picture: *const WebPPicture,
+) -> c_int {
+    let sink = unsafe { &mut *((*picture).custom_ptr as *mut WriterSink<W>) };
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        unsafe { slice::from_raw_parts(data, data_size) }
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| sink.writer.write_all(data))) {
+        Ok(Ok(())) => 1,
+        Ok(Err(e)) => {
+            sink.error = Some(e);
+            0
+        }
+        Err(payload) => {
+            sink.panic = Some(payload);
+            0
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::compat::*;
+use crate::decode::*;
+use crate::encode::*;
+#[cfg(feature = "mux")]
+use crate::mux::*;
+
+/// Convenient alias for results returned by the safe wrappers.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors returned by the safe wrappers.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The linked libwebp rejected the ABI version this crate was built for.
+    VersionMismatch,
+    /// The libwebp loaded at runtime is older than the enabled features
+    /// require. Only checked with the `runtime-check` feature.
+    RuntimeCompat(RuntimeCompatError),
+    /// The input buffer is shorter than its dimensions require.
+    BufferTooSmall {
+        /// Number of bytes required.
+        expected: usize,
+        /// Number of bytes supplied.
+        actual: usize,
+    },
+    /// Decoding failed with the given status.
+    Decode(DecodeError),
+    /// Encoding failed with the given error code.
+    Encode(EncodeError),
+    /// A mux operation failed.
+    #[cfg(feature = "mux")]
+    Mux(MuxError),
+    /// Animation encoding failed.
+    #[cfg(all(feature = "mux", feature = "0_5"))]
+    AnimEncode(AnimEncodeError),
+    /// Reading input or writing output failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::VersionMismatch => f.write_str("libwebp ABI version mismatch"),
+            Error::RuntimeCompat(e) => e.fmt(f),
+            Error::BufferTooSmall { expected, actual } => write!(
+                f,
+                "buffer too small: expected {} bytes, got {}",
+                expected, actual
+            ),
+            Error::Decode(e) => write!(f, "decoding failed: {}", e),
+            Error::Encode(e) => write!(f, "encoding failed: {}", e),
+            #[cfg(feature = "mux")]
+            Error::Mux(e) => write!(f, "mux operation failed: {}", e),
+            #[cfg(all(feature = "mux", feature = "0_5"))]
+            Error::AnimEncode(e) => write!(f, "animation encoding failed: {}", e),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::RuntimeCompat(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            Error::Encode(e) => Some(e),
+            #[cfg(feature = "mux")]
+            Error::Mux(e) => Some(e),
+            #[cfg(all(feature = "mux", feature = "0_5"))]
+            Error::AnimEncode(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::VersionMismatch | Error::BufferTooSmall { .. } => None,
+        }
+    }
+}
+
+impl From<RuntimeCompatError> for Error {
+    fn from(e: RuntimeCompatError) -> Self {
+        Error::RuntimeCompat(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(e: EncodeError) -> Self {
+        Error::Encode(e)
+    }
+}
+
+#[cfg(feature = "mux")]
+impl From<MuxError> for Error {
+    fn from(e: MuxError) -> Self {
+        Error::Mux(e)
+    }
+}
+
+#[cfg(all(feature = "mux", feature = "0_5"))]
+impl From<AnimEncodeError> for Error {
+    fn from(e: AnimEncodeError) -> Self {
+        Error::AnimEncode(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Runs [`check_runtime_compat`] once, before the first call into libwebp,
+/// when the `runtime-check` feature is enabled.
+pub(crate) fn ensure_runtime_compat() -> Result<()> {
+    #[cfg(feature = "runtime-check")]
+    {
+        use std::sync::OnceLock;
+
+        static RESULT: OnceLock<Result<(), RuntimeCompatError>> = OnceLock::new();
+        (*RESULT.get_or_init(check_runtime_compat))?;
+    }
+    Ok(())
+}
+
+/// Non-successful values of [`VP8StatusCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// `VP8_STATUS_OUT_OF_MEMORY`
+    OutOfMemory,
+    /// `VP8_STATUS_INVALID_PARAM`
+    InvalidParam,
+    /// `VP8_STATUS_BITSTREAM_ERROR`
+    BitstreamError,
+    /// `VP8_STATUS_UNSUPPORTED_FEATURE`
+    UnsupportedFeature,
+    /// `VP8_STATUS_SUSPENDED`
+    Suspended,
+    /// `VP8_STATUS_USER_ABORT`
+    UserAbort,
+    /// `VP8_STATUS_NOT_ENOUGH_DATA`
+    NotEnoughData,
+}
+
+impl DecodeError {
+    /// Turns a status code returned by libwebp into a `Result`.
+    pub(crate) fn check(status: VP8StatusCode) -> Result<(), DecodeError> {
+        match DecodeError::try_from(status) {
+            Ok(e) => Err(e),
+            Err(VP8_STATUS_OK) => Ok(()),
+            // libwebp does not return anything else; treat it as corrupt input.
+            Err(_) => Err(DecodeError::BitstreamError),
+        }
+    }
+}
+
+/// Fails with the original code for `VP8_STATUS_OK` and unknown values.
+impl TryFrom<VP8StatusCode> for DecodeError {
+    type Error = VP8StatusCode;
+
+    fn try_from(status: VP8StatusCode) -> Result<Self, Self::Error> {
+        Ok(match status {
+            VP8_STATUS_OUT_OF_MEMORY => DecodeError::OutOfMemory,
+            VP8_STATUS_INVALID_PARAM => DecodeError::InvalidParam,
+            VP8_STATUS_BITSTREAM_ERROR => DecodeError::BitstreamError,
+            VP8_STATUS_UNSUPPORTED_FEATURE => DecodeError::UnsupportedFeature,
+            VP8_STATUS_SUSPENDED => DecodeError::Suspended,
+            VP8_STATUS_USER_ABORT => DecodeError::UserAbort,
+            VP8_STATUS_NOT_ENOUGH_DATA => DecodeError::NotEnoughData,
+            _ => return Err(status),
+        })
+    }
+}
+
+impl From<DecodeError> for VP8StatusCode {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::OutOfMemory => VP8_STATUS_OUT_OF_MEMORY,
+            DecodeError::InvalidParam => VP8_STATUS_INVALID_PARAM,
+            DecodeError::BitstreamError => VP8_STATUS_BITSTREAM_ERROR,
+            DecodeError::UnsupportedFeature => VP8_STATUS_UNSUPPORTED_FEATURE,
+            DecodeError::Suspended => VP8_STATUS_SUSPENDED,
+            DecodeError::UserAbort => VP8_STATUS_USER_ABORT,
+            DecodeError::NotEnoughData => VP8_STATUS_NOT_ENOUGH_DATA,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DecodeError::OutOfMemory => "out of memory",
+            DecodeError::InvalidParam => "invalid parameter",
+            DecodeError::BitstreamError => "bitstream error",
+            DecodeError::UnsupportedFeature => "unsupported feature",
+            DecodeError::Suspended => "suspended",
+            DecodeError::UserAbort => "aborted by user",
+            DecodeError::NotEnoughData => "not enough data",
+        })
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Non-successful values of [`WebPEncodingError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// `VP8_ENC_ERROR_OUT_OF_MEMORY`
+    OutOfMemory,
+    /// `VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY`
+    BitstreamOutOfMemory,
+    /// `VP8_ENC_ERROR_NULL_PARAMETER`
+    NullParameter,
+    /// `VP8_ENC_ERROR_INVALID_CONFIGURATION`
+    InvalidConfiguration,
+    /// `VP8_ENC_ERROR_BAD_DIMENSION`
+    BadDimension,
+    /// `VP8_ENC_ERROR_PARTITION0_OVERFLOW`
+    Partition0Overflow,
+    /// `VP8_ENC_ERROR_PARTITION_OVERFLOW`
+    PartitionOverflow,
+    /// `VP8_ENC_ERROR_BAD_WRITE`
+    BadWrite,
+    /// `VP8_ENC_ERROR_FILE_TOO_BIG`
+    FileTooBig,
+    /// `VP8_ENC_ERROR_USER_ABORT`
+    UserAbort,
+}
+
+impl EncodeError {
+    /// Reads the error recorded in `picture.error_code` after a failed call.
+    pub(crate) fn from_picture(picture: &WebPPicture) -> EncodeError {
+        // Allocation failures are not always recorded by older versions.
+        EncodeError::try_from(picture.error_code).unwrap_or(EncodeError::OutOfMemory)
+    }
+}
+
+/// Fails with the original code for `VP8_ENC_OK` and unknown values.
+impl TryFrom<WebPEncodingError> for EncodeError {
+    type Error = WebPEncodingError;
+
+    fn try_from(code: WebPEncodingError) -> Result<Self, Self::Error> {
+        Ok(match code {
+            VP8_ENC_ERROR_OUT_OF_MEMORY => EncodeError::OutOfMemory,
+            VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY => EncodeError::BitstreamOutOfMemory,
+            VP8_ENC_ERROR_NULL_PARAMETER => EncodeError::NullParameter,
+            VP8_ENC_ERROR_INVALID_CONFIGURATION => EncodeError::InvalidConfiguration,
+            VP8_ENC_ERROR_BAD_DIMENSION => EncodeError::BadDimension,
+            VP8_ENC_ERROR_PARTITION0_OVERFLOW => EncodeError::Partition0Overflow,
+            VP8_ENC_ERROR_PARTITION_OVERFLOW => EncodeError::PartitionOverflow,
+            VP8_ENC_ERROR_BAD_WRITE => EncodeError::BadWrite,
+            VP8_ENC_ERROR_FILE_TOO_BIG => EncodeError::FileTooBig,
+            VP8_ENC_ERROR_USER_ABORT => EncodeError::UserAbort,
+            _ => return Err(code),
+        })
+    }
+}
+
+impl From<EncodeError> for WebPEncodingError {
+    fn from(e: EncodeError) -> Self {
+        match e {
+            EncodeError::OutOfMemory => VP8_ENC_ERROR_OUT_OF_MEMORY,
+            EncodeError::BitstreamOutOfMemory => VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY,
+            EncodeError::NullParameter => VP8_ENC_ERROR_NULL_PARAMETER,
+            EncodeError::InvalidConfiguration => VP8_ENC_ERROR_INVALID_CONFIGURATION,
+            EncodeError::BadDimension => VP8_ENC_ERROR_BAD_DIMENSION,
+            EncodeError::Partition0Overflow => VP8_ENC_ERROR_PARTITION0_OVERFLOW,
+            EncodeError::PartitionOverflow => VP8_ENC_ERROR_PARTITION_OVERFLOW,
+            EncodeError::BadWrite => VP8_ENC_ERROR_BAD_WRITE,
+            EncodeError::FileTooBig => VP8_ENC_ERROR_FILE_TOO_BIG,
+            EncodeError::UserAbort => VP8_ENC_ERROR_USER_ABORT,
+        }
+    }
+}
+
+/// The messages follow the comments in `encode.h`.
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EncodeError::OutOfMemory => "memory error allocating objects",
+            EncodeError::BitstreamOutOfMemory => "memory error while flushing bits",
+            EncodeError::NullParameter => "a pointer parameter is NULL",
+            EncodeError::InvalidConfiguration => "configuration is invalid",
+            EncodeError::BadDimension => "picture has invalid width/height",
+            EncodeError::Partition0Overflow => "partition is bigger than 512k",
+            EncodeError::PartitionOverflow => "partition is bigger than 16M",
+            EncodeError::BadWrite => "error while flushing bytes",
+            EncodeError::FileTooBig => "file is bigger than 4G",
+            EncodeError::UserAbort => "abort request by user",
+        })
+    }
+}
+
+impl error::Error for EncodeError {}
+
+/// Non-successful values of [`WebPMuxError`].
+#[cfg(feature = "mux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MuxError {
+    /// `WEBP_MUX_NOT_FOUND`
+    NotFound,
+    /// `WEBP_MUX_INVALID_ARGUMENT`
+    InvalidArgument,
+    /// `WEBP_MUX_BAD_DATA`
+    BadData,
+    /// `WEBP_MUX_MEMORY_ERROR`
+    MemoryError,
+    /// `WEBP_MUX_NOT_ENOUGH_DATA`
+    NotEnoughData,
+}
+
+#[cfg(feature = "mux")]
+impl MuxError {
+    /// Turns an error code returned by libwebpmux into a `Result`.
+    pub(crate) fn check(code: WebPMuxError) -> Result<(), MuxError> {
+        match MuxError::try_from(code) {
+            Ok(e) => Err(e),
+            Err(WEBP_MUX_OK) => Ok(()),
+            // libwebpmux does not return anything else; treat it as corrupt input.
+            Err(_) => Err(MuxError::BadData),
+        }
+    }
+}
+
+/// Fails with the original code for `WEBP_MUX_OK` and unknown values.
+#[cfg(feature = "mux")]
+impl TryFrom<WebPMuxError> for MuxError {
+    type Error = WebPMuxError;
+
+    fn try_from(code: WebPMuxError) -> Result<Self, Self::Error> {
+        Ok(match code {
+            WEBP_MUX_NOT_FOUND => MuxError::NotFound,
+            WEBP_MUX_INVALID_ARGUMENT => MuxError::InvalidArgument,
+            WEBP_MUX_BAD_DATA => MuxError::BadData,
+            WEBP_MUX_MEMORY_ERROR => MuxError::MemoryError,
+            WEBP_MUX_NOT_ENOUGH_DATA => MuxError::NotEnoughData,
+            _ => return Err(code),
+        })
+    }
+}
+
+#[cfg(feature = "mux")]
+impl From<MuxError> for WebPMuxError {
+    fn from(e: MuxError) -> Self {
+        match e {
+            MuxError::NotFound => WEBP_MUX_NOT_FOUND,
+            MuxError::InvalidArgument => WEBP_MUX_INVALID_ARGUMENT,
+            MuxError::BadData => WEBP_MUX_BAD_DATA,
+            MuxError::MemoryError => WEBP_MUX_MEMORY_ERROR,
+            MuxError::NotEnoughData => WEBP_MUX_NOT_ENOUGH_DATA,
+        }
+    }
+}
+
+#[cfg(feature = "mux")]
+impl fmt::Display for MuxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MuxError::NotFound => "not found",
+            MuxError::InvalidArgument => "invalid argument",
+            MuxError::BadData => "bad data",
+            MuxError::MemoryError => "memory error",
+            MuxError::NotEnoughData => "not enough data",
+        })
+    }
+}
+
+#[cfg(feature = "mux")]
+impl error::Error for MuxError {}
+
+/// An error reported by `WebPAnimEncoderGetError`.
+#[cfg(all(feature = "mux", feature = "0_5"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnimEncodeError {
+    message: String,
+}
+
+#[cfg(all(feature = "mux", feature = "0_5"))]
+impl AnimEncodeError {
+    pub(crate) fn new(message: String) -> Self {
+        AnimEncodeError { message }
+    }
+
+    /// The message reported by libwebp.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+#[cfg(all(feature = "mux", feature = "0_5"))]
+impl fmt::Display for AnimEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(all(feature = "mux", feature = "0_5"))]
+impl error::Error for AnimEncodeError {}
@@ -0,0 +1,302 @@
+use std::error;
+use std::fmt;
+use std::io::{self, Read};
+use std::mem::{self, ManuallyDrop};
+use std::os::raw::*;
+use std::ptr::{self, NonNull};
+
+use crate::decode::*;
+use crate::safe::decode::*;
+use crate::safe::error::*;
+
+/// Size of the reads issued by [`IncrementalDecoder::read_from`].
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Progress reported by [`IncrementalDecoder::append`] and
+/// [`IncrementalDecoder::update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IncrementalStatus {
+    /// The image is not complete yet (`VP8_STATUS_SUSPENDED`).
+    NeedMoreData,
+    /// The whole image has been decoded.
+    Complete,
+}
+
+/// A push-style decoder owning a [`WebPIDecoder`].
+///
+/// ```no_run
+/// use libwebp_sys::safe::{ColorMode, IncrementalDecoder, IncrementalStatus};
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// # let chunks: Vec<Vec<u8>> = vec![];
+/// let mut idec = IncrementalDecoder::new(ColorMode::Rgba)?;
+/// for chunk in chunks {
+///     if idec.append(&chunk)? == IncrementalStatus::Complete {
+///         break;
+///     }
+///     if let Some(partial) = idec.decoded() {
+///         println!("{} rows ready", partial.decoded_rows());
+///     }
+/// }
+/// let image = idec.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct IncrementalDecoder {
+    idec: NonNull<WebPIDecoder>,
+    // libwebp keeps pointers into the config (and its output buffer) for the
+    // whole lifetime of the decoder, so it must not move.
+    config: Box<WebPDecoderConfig>,
+    status: IncrementalStatus,
+}
+
+// The decoder has no thread affinity and is only used through `&mut self`.
+unsafe impl Send for IncrementalDecoder {}
+
+impl IncrementalDecoder {
+    /// Creates a decoder writing to an internally allocated buffer of the
+    /// given colorspace, using `WebPINewDecoder`.
+    pub fn new(color_mode: ColorMode) -> Result<Self> {
+        ensure_runtime_compat()?;
+        let mut config: Box<WebPDecoderConfig> = Box::new(unsafe { mem::zeroed() });
+        if unsafe { WebPInitDecoderConfig(&mut *config) } == 0 {
+            return Err(Error::VersionMismatch);
+        }
+        config.output.colorspace = color_mode.to_raw();
+        let idec = unsafe { WebPINewDecoder(&mut config.output) };
+        Self::from_raw_parts(idec, config)
+    }
+
+    /// Creates a decoder honouring all options of `decoder`, using
+    /// `WebPIDecode`.
+    pub fn with_options(decoder: &Decoder) -> Result<Self> {
+        let mut config = Box::new(decoder.to_config()?);
+        let idec = unsafe { WebPIDecode(ptr::null(), 0, &mut *config) };
+        Self::from_raw_parts(idec, config)
+    }
+
+    fn from_raw_parts(idec: *mut WebPIDecoder, config: Box<WebPDecoderConfig>) -> Result<Self> {
+        let idec = NonNull::new(idec).ok_or(DecodeError::OutOfMemory)?;
+        Ok(IncrementalDecoder {
+            idec,
+            config,
+            status: IncrementalStatus::NeedMoreData,
+        })
+    }
+
+    fn check(&mut self, status: VP8StatusCode) -> Result<IncrementalStatus> {
+        self.status = match DecodeError::check(status) {
+            Ok(()) => IncrementalStatus::Complete,
+            Err(DecodeError::Suspended) => IncrementalStatus::NeedMoreData,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(self.status)
+    }
+
+    /// Copies and decodes the next chunk of data (`WebPIAppend`).
+    pub fn append(&mut self, data: &[u8]) -> Result<IncrementalStatus> {
+        let status = unsafe { WebPIAppend(self.idec.as_ptr(), data.as_ptr(), data.len()) };
+        self.check(status)
+    }
+
+    /// Decodes from a buffer holding all data received so far, from the
+    /// beginning of the stream (`WebPIUpdate`). The data is not copied, so
+    /// each call must pass the whole, possibly reallocated, buffer.
+    ///
+    /// `append` and `update` cannot be mixed on the same decoder.
+    pub fn update(&mut self, data: &[u8]) -> Result<IncrementalStatus> {
+        let status = unsafe { WebPIUpdate(self.idec.as_ptr(), data.as_ptr(), data.len()) };
+        self.check(status)
+    }
+
+    /// The status after the last call to `append` or `update`.
+    pub fn status(&self) -> IncrementalStatus {
+        self.status
+    }
+
+    /// The rows decoded so far, or `None` if decoding has not produced any
+    /// output yet (`WebPIDecodedArea`).
+    ///
+    /// Always `None` when decoding with [`Decoder::flip`], since the output
+    /// is written bottom-up until decoding completes.
+    pub fn decoded(&self) -> Option<PartialImage<'_>> {
+        #[cfg(feature = "0_5")]
+        if self.config.options.flip != 0 {
+            return None;
+        }
+        let mut height: c_int = 0;
+        let buffer = unsafe {
+            WebPIDecodedArea(
+                self.idec.as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut height,
+            )
+        };
+        let buffer = unsafe { buffer.as_ref()? };
+        if buffer.width <= 0 || buffer.height <= 0 {
+            return None;
+        }
+        Some(PartialImage {
+            buffer,
+            rows: height.clamp(0, buffer.height) as u32,
+        })
+    }
+
+    /// Returns the decoded image once decoding is complete.
+    pub fn finish(self) -> Result<DecodedImage> {
+        if self.status != IncrementalStatus::Complete {
+            return Err(DecodeError::NotEnoughData.into());
+        }
+        Ok(self.into_image())
+    }
+
+    /// Hands over the output buffer. Only valid once decoding is complete.
+    fn into_image(self) -> DecodedImage {
+        let this = ManuallyDrop::new(self);
+        unsafe { WebPIDelete(this.idec.as_ptr()) };
+        // The decoder is gone, so the output buffer can be handed over.
+        let config = unsafe { ptr::read(&this.config) };
+        unsafe { DecodedImage::from_raw(config.output) }
+    }
+
+    /// Pulls data from `reader` in fixed-size chunks and appends it until the
+    /// image is complete, then returns it. No further reads are issued once
+    /// the image is complete, but the last read may have consumed data past
+    /// its end.
+    ///
+    /// If the reader fails or reaches EOF early, or the data is invalid, the
+    /// returned [`ReadError`] keeps the decoder so that the rows decoded so
+    /// far remain accessible. Early EOF is reported as
+    /// [`DecodeError::NotEnoughData`].
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use libwebp_sys::safe::{ColorMode, IncrementalDecoder};
+    ///
+    /// # fn main() -> libwebp_sys::safe::Result<()> {
+    /// let file = File::open("image.webp")?;
+    /// let image = IncrementalDecoder::new(ColorMode::Rgba)?.read_from(file)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_from<R: Read>(mut self, mut reader: R) -> Result<DecodedImage, ReadError> {
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        while self.status != IncrementalStatus::Complete {
+            let len = match reader.read(&mut chunk) {
+                Ok(0) => return Err(ReadError::new(self, DecodeError::NotEnoughData.into())),
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ReadError::new(self, e.into())),
+            };
+            if let Err(e) = self.append(&chunk[..len]) {
+                return Err(ReadError::new(self, e));
+            }
+        }
+        Ok(self.into_image())
+    }
+}
+
+impl Drop for IncrementalDecoder {
+    fn drop(&mut self) {
+        unsafe {
+            WebPIDelete(self.idec.as_ptr());
+            WebPFreeDecBuffer(&mut self.config.output);
+        }
+    }
+}
+
+/// The part of an image decoded so far, borrowed from an
+/// [`IncrementalDecoder`].
+#[derive(Debug, Clone, Copy)]
+pub struct PartialImage<'a> {
+    buffer: &'a WebPDecBuffer,
+    rows: u32,
+}
+
+impl<'a> PartialImage<'a> {
+    /// Width of the full image in pixels.
+    pub fn width(&self) -> u32 {
+        self.buffer.width as u32
+    }
+
+    /// Height of the full image in pixels.
+    pub fn height(&self) -> u32 {
+        self.buffer.height as u32
+    }
+
+    /// Number of rows decoded so far.
+    pub fn decoded_rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// The colorspace of the samples.
+    pub fn color_mode(&self) -> ColorMode {
+        ColorMode::from_raw(self.buffer.colorspace).expect("libwebp returned an invalid colorspace")
+    }
+
+    /// The decoded rows of packed samples, or `None` for YUV output.
+    pub fn rgb(&self) -> Option<Plane<'a>> {
+        unsafe { rgb_plane(self.buffer, self.rows) }
+    }
+
+    /// The decoded rows of each plane, or `None` for RGB output.
+    pub fn yuva(&self) -> Option<YuvaPlanes<'a>> {
+        unsafe { yuva_planes(self.buffer, self.rows) }
+    }
+}
+
+/// The error returned by [`IncrementalDecoder::read_from`], holding the
+/// decoder with whatever was decoded before the failure.
+#[derive(Debug)]
+pub struct ReadError {
+    decoder: IncrementalDecoder,
+    error: Error,
+}
+
+impl ReadError {
+    fn new(decoder: IncrementalDecoder, error: Error) -> Self {
+        ReadError { decoder, error }
+    }
+
+    /// The reason decoding stopped.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// The rows decoded before the failure, if any.
+    pub fn partial(&self) -> Option<PartialImage<'_>> {
+        self.decoder.decoded()
+    }
+
+    /// Returns the decoder, e.g. to resume with [`IncrementalDecoder::append`]
+    /// once more data is available.
+    pub fn into_decoder(self) -> IncrementalDecoder {
+        self.decoder
+    }
+
+    /// Discards the partial output and returns the error.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+impl From<ReadError> for Error {
+    fn from(e: ReadError) -> Self {
+        e.error
+    }
+}
@@ -0,0 +1,129 @@
+//! Reading, embedding and stripping EXIF, ICC profile and XMP metadata.
+//!
+//! Reading requires the `demux` feature; embedding and stripping require the
+//! `mux` feature. All functions accept still and animated images alike.
+//!
+//! ```no_run
+//! use libwebp_sys::safe::metadata::{self, Metadata};
+//!
+//! # fn main() -> libwebp_sys::safe::Result<()> {
+//! # let data: &[u8] = &[];
+//! # let icc_profile = vec![];
+//! let original = metadata::read(data)?;
+//! let tagged = metadata::embed(data, &Metadata {
+//!     icc_profile: Some(icc_profile),
+//!     ..Metadata::default()
+//! })?;
+//! let anonymized = metadata::strip(&tagged)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "demux")]
+use crate::safe::demux::Demuxer;
+use crate::safe::error::*;
+#[cfg(feature = "mux")]
+use crate::safe::mux::Mux;
+
+/// A kind of metadata chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataKind {
+    /// The `EXIF` chunk.
+    Exif,
+    /// The `ICCP` chunk.
+    IccProfile,
+    /// The `XMP ` chunk.
+    Xmp,
+}
+
+impl MetadataKind {
+    /// All kinds of metadata.
+    pub const ALL: [MetadataKind; 3] = [
+        MetadataKind::Exif,
+        MetadataKind::IccProfile,
+        MetadataKind::Xmp,
+    ];
+
+    /// The FourCC of the chunk.
+    pub fn fourcc(self) -> &'static [u8; 4] {
+        match self {
+            MetadataKind::Exif => b"EXIF",
+            MetadataKind::IccProfile => b"ICCP",
+            MetadataKind::Xmp => b"XMP ",
+        }
+    }
+}
+
+/// The metadata chunks of an image.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Metadata {
+    /// The payload of the `EXIF` chunk.
+    pub exif: Option<Vec<u8>>,
+    /// The payload of the `ICCP` chunk.
+    pub icc_profile: Option<Vec<u8>>,
+    /// The payload of the `XMP ` chunk.
+    pub xmp: Option<Vec<u8>>,
+}
+
+impl Metadata {
+    /// The payload of the given kind.
+    pub fn get(&self, kind: MetadataKind) -> Option<&[u8]> {
+        match kind {
+            MetadataKind::Exif => self.exif.as_deref(),
+            MetadataKind::IccProfile => self.icc_profile.as_deref(),
+            MetadataKind::Xmp => self.xmp.as_deref(),
+        }
+    }
+
+    /// True if no metadata is present.
+    pub fn is_empty(&self) -> bool {
+        MetadataKind::ALL
+            .iter()
+            .all(|&kind| self.get(kind).is_none())
+    }
+}
+
+/// Extracts the metadata of a WebP file. Only the first chunk of each kind
+/// is returned.
+#[cfg(feature = "demux")]
+pub fn read(data: &[u8]) -> Result<Metadata> {
+    let demuxer = Demuxer::new(data)?;
+    let chunk = |kind: MetadataKind| demuxer.chunks(kind.fourcc()).next().map(<[u8]>::to_vec);
+    Ok(Metadata {
+        exif: chunk(MetadataKind::Exif),
+        icc_profile: chunk(MetadataKind::IccProfile),
+        xmp: chunk(MetadataKind::Xmp),
+    })
+}
+
+/// Returns a copy of a WebP file with the present fields of `metadata`
+/// embedded, replacing existing chunks of the same kind. Other chunks are
+/// kept, and the `VP8X` flags are updated to match.
+#[cfg(feature = "mux")]
+pub fn embed(data: &[u8], metadata: &Metadata) -> Result<Vec<u8>> {
+    let mut mux = Mux::from_bytes(data)?;
+    for kind in MetadataKind::ALL {
+        if let Some(payload) = metadata.get(kind) {
+            mux.set_chunk(kind.fourcc(), payload)?;
+        }
+    }
+    mux.assemble()
+}
+
+/// Returns a copy of a WebP file without the given kinds of metadata. The
+/// `VP8X` chunk is updated, or dropped when no longer needed.
+#[cfg(feature = "mux")]
+pub fn remove(data: &[u8], kinds: &[MetadataKind]) -> Result<Vec<u8>> {
+    let mut mux = Mux::from_bytes(data)?;
+    for kind in kinds {
+        mux.delete_chunk(kind.fourcc())?;
+    }
+    mux.assemble()
+}
+
+/// Returns a copy of a WebP file without any EXIF, ICC profile or XMP
+/// metadata.
+#[cfg(feature = "mux")]
+pub fn strip(data: &[u8]) -> Result<Vec<u8>> {
+    remove(data, &MetadataKind::ALL)
+}
@@ -0,0 +1,267 @@
+use std::mem;
+use std::os::raw::*;
+use std::ptr::NonNull;
+
+use crate::mux::*;
+use crate::mux_types::*;
+use crate::safe::error::*;
+use crate::safe::mux_types::*;
+
+/// Placement and timing of an animation frame, mirroring
+/// [`WebPMuxFrameInfo`] without the bitstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FrameInfo {
+    /// Horizontal offset of the frame on the canvas. Must be even.
+    pub x_offset: u32,
+    /// Vertical offset of the frame on the canvas. Must be even.
+    pub y_offset: u32,
+    /// Display duration in milliseconds.
+    pub duration: u32,
+    /// How the frame is treated after it has been displayed.
+    pub dispose_method: DisposeMethod,
+    /// How the frame is combined with the canvas.
+    pub blend_method: BlendMethod,
+}
+
+/// A frame returned by [`Mux::frame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuxFrame {
+    /// The frame's bitstream (the `ALPH` and `VP8 ` or `VP8L` chunks).
+    pub bitstream: Vec<u8>,
+    /// Placement and timing of the frame. Defaults for still images.
+    pub info: FrameInfo,
+}
+
+/// Animation parameters, mirroring [`WebPMuxAnimParams`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnimParams {
+    /// Background color in `[Blue, Green, Red, Alpha]` byte order.
+    pub bgcolor: u32,
+    /// Number of times to play the animation; 0 means infinitely.
+    pub loop_count: u32,
+}
+
+impl Default for AnimParams {
+    fn default() -> Self {
+        AnimParams {
+            bgcolor: 0xffffffff,
+            loop_count: 0,
+        }
+    }
+}
+
+fn to_c_int(value: u32) -> Result<c_int> {
+    c_int::try_from(value).map_err(|_| Error::Mux(MuxError::InvalidArgument))
+}
+
+fn fourcc_ptr(fourcc: &[u8; 4]) -> *const c_char {
+    fourcc.as_ptr() as *const c_char
+}
+
+fn to_data(data: &[u8]) -> WebPData {
+    WebPData {
+        bytes: data.as_ptr(),
+        size: data.len(),
+    }
+}
+
+/// Maps `WEBP_MUX_NOT_FOUND` to `Ok(false)`.
+fn check_found(code: WebPMuxError) -> Result<bool> {
+    match MuxError::check(code) {
+        Ok(()) => Ok(true),
+        Err(MuxError::NotFound) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// An editable WebP container owning a [`WebPMux`].
+///
+/// All data passed in is copied, so the mux does not borrow anything. The
+/// mux is released with `WebPMuxDelete` on drop.
+///
+/// ```no_run
+/// use libwebp_sys::safe::Mux;
+///
+/// # fn main() -> libwebp_sys::safe::Result<()> {
+/// # let data: &[u8] = &[];
+/// # let icc_profile: &[u8] = &[];
+/// let mut mux = Mux::from_bytes(data)?;
+/// mux.set_chunk(b"ICCP", icc_profile)?;
+/// mux.delete_chunk(b"EXIF")?;
+/// let webp = mux.assemble()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Mux {
+    mux: NonNull<WebPMux>,
+}
+
+// The mux owns all of its data and is only modified through `&mut self`.
+unsafe impl Send for Mux {}
+unsafe impl Sync for Mux {}
+
+impl Mux {
+    /// Creates an empty mux.
+    pub fn new() -> Result<Self> {
+        ensure_runtime_compat()?;
+        let mux = unsafe { WebPMuxNew() };
+        let mux = NonNull::new(mux).ok_or(MuxError::MemoryError)?;
+        Ok(Mux { mux })
+    }
+
+    /// Parses a WebP file, or a raw `VP8 `/`VP8L` bitstream, copying its
+    /// data.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        ensure_runtime_compat()?;
+        let data = to_data(data);
+        let mux = unsafe { WebPMuxCreate(&data, 1) };
+        let mux = NonNull::new(mux).ok_or(MuxError::BadData)?;
+        Ok(Mux { mux })
+    }
+
+    /// Adds a chunk with the given FourCC, replacing any existing one.
+    /// Image and animation chunks must be set through the dedicated methods.
+    pub fn set_chunk(&mut self, fourcc: &[u8; 4], data: &[u8]) -> Result<()> {
+        let data = to_data(data);
+        let code = unsafe { WebPMuxSetChunk(self.mux.as_ptr(), fourcc_ptr(fourcc), &data, 1) };
+        MuxError::check(code)?;
+        Ok(())
+    }
+
+    /// The payload of the chunk with the given FourCC, if present.
+    pub fn chunk(&self, fourcc: &[u8; 4]) -> Result<Option<&[u8]>> {
+        let mut data: WebPData = unsafe { mem::zeroed() };
+        let code = unsafe { WebPMuxGetChunk(self.mux.as_ptr(), fourcc_ptr(fourcc), &mut data) };
+        if !check_found(code)? {
+            return Ok(None);
+        }
+        // The payload is owned by the mux.
+        Ok(Some(unsafe { data_slice(&data) }))
+    }
+
+    /// Deletes all chunks with the given FourCC. Returns false if there were
+    /// none.
+    pub fn delete_chunk(&mut self, fourcc: &[u8; 4]) -> Result<bool> {
+        check_found(unsafe { WebPMuxDeleteChunk(self.mux.as_ptr(), fourcc_ptr(fourcc)) })
+    }
+
+    /// Replaces all images and frames with a single still image.
+    pub fn set_image(&mut self, bitstream: &[u8]) -> Result<()> {
+        let data = to_data(bitstream);
+        let code = unsafe { WebPMuxSetImage(self.mux.as_ptr(), &data, 1) };
+        MuxError::check(code)?;
+        Ok(())
+    }
+
+    /// Appends an animation frame.
+    pub fn push_frame(&mut self, bitstream: &[u8], info: &FrameInfo) -> Result<()> {
+        let mut frame: WebPMuxFrameInfo = unsafe { mem::zeroed() };
+        frame.bitstream = to_data(bitstream);
+        frame.x_offset = to_c_int(info.x_offset)?;
+        frame.y_offset = to_c_int(info.y_offset)?;
+        frame.duration = to_c_int(info.duration)?;
+        frame.id = WEBP_CHUNK_ANMF;
+        frame.dispose_method = info.dispose_method.to_raw();
+        frame.blend_method = info.blend_method.to_raw();
+        let code = unsafe { WebPMuxPushFrame(self.mux.as_ptr(), &frame, 1) };
+        MuxError::check(code)?;
+        Ok(())
+    }
+
+    /// Returns a copy of the frame with the given 1-based index, if present.
+    /// Index 0 returns the last frame.
+    pub fn frame(&self, frame_number: u32) -> Result<Option<MuxFrame>> {
+        let mut frame: WebPMuxFrameInfo = unsafe { mem::zeroed() };
+        let code = unsafe { WebPMuxGetFrame(self.mux.as_ptr(), frame_number, &mut frame) };
+        if !check_found(code)? {
+            return Ok(None);
+        }
+        // The bitstream is a copy that has to be freed here.
+        let bitstream = unsafe { data_slice(&frame.bitstream) }.to_vec();
+        unsafe { WebPDataClear(&mut frame.bitstream) };
+        let info = FrameInfo {
+            x_offset: frame.x_offset as u32,
+            y_offset: frame.y_offset as u32,
+            duration: frame.duration as u32,
+            dispose_method: DisposeMethod::from_raw(frame.dispose_method).unwrap_or_default(),
+            blend_method: BlendMethod::from_raw(frame.blend_method).unwrap_or_default(),
+        };
+        Ok(Some(MuxFrame { bitstream, info }))
+    }
+
+    /// Deletes the frame with the given 1-based index. Index 0 deletes the
+    /// last frame. Returns false if there was no such frame.
+    pub fn delete_frame(&mut self, frame_number: u32) -> Result<bool> {
+        check_found(unsafe { WebPMuxDeleteFrame(self.mux.as_ptr(), frame_number) })
+    }
+
+    /// Sets the animation parameters.
+    pub fn set_animation_params(&mut self, params: &AnimParams) -> Result<()> {
+        let params = WebPMuxAnimParams {
+            bgcolor: params.bgcolor,
+            loop_count: to_c_int(params.loop_count)?,
+        };
+        let code = unsafe { WebPMuxSetAnimationParams(self.mux.as_ptr(), &params) };
+        MuxError::check(code)?;
+        Ok(())
+    }
+
+    /// The animation parameters, or `None` if there is no `ANIM` chunk.
+    pub fn animation_params(&self) -> Result<Option<AnimParams>> {
+        let mut params: WebPMuxAnimParams = unsafe { mem::zeroed() };
+        let code = unsafe { WebPMuxGetAnimationParams(self.mux.as_ptr(), &mut params) };
+        if !check_found(code)? {
+            return Ok(None);
+        }
+        Ok(Some(AnimParams {
+            bgcolor: params.bgcolor,
+            loop_count: params.loop_count as u32,
+        }))
+    }
+
+    /// Sets the canvas size. `(0, 0)` derives it from the frames when
+    /// assembling.
+    #[cfg(feature = "0_5")]
+    pub fn set_canvas_size(&mut self, width: u32, height: u32) -> Result<()> {
+        let (width, height) = (to_c_int(width)?, to_c_int(height)?);
+        let code = unsafe { WebPMuxSetCanvasSize(self.mux.as_ptr(), width, height) };
+        MuxError::check(code)?;
+        Ok(())
+    }
+
+    /// The canvas size, as `(width, height)`.
+    pub fn canvas_size(&self) -> Result<(u32, u32)> {
+        let (mut width, mut height) = (0, 0);
+        let code = unsafe { WebPMuxGetCanvasSize(self.mux.as_ptr(), &mut width, &mut height) };
+        MuxError::check(code)?;
+        Ok((width as u32, height as u32))
+    }
+
+    /// The `*_FLAG` bits describing the content. For a mux parsed from a file
+    /// with a `VP8X` chunk, these are the stored flags, which are only
+    /// recomputed by [`Mux::assemble`].
+    pub fn features(&self) -> Result<WebPFeatureFlags> {
+        let mut flags = 0;
+        MuxError::check(unsafe { WebPMuxGetFeatures(self.mux.as_ptr(), &mut flags) })?;
+        Ok(flags)
+    }
+
+    /// Assembles all chunks into a WebP file. The `VP8X` chunk is updated
+    /// to match the content.
+    pub fn assemble(&mut self) -> Result<Vec<u8>> {
+        let mut data: WebPData = unsafe { mem::zeroed() };
+        let code = unsafe { WebPMuxAssemble(self.mux.as_ptr(), &mut data) };
+        // Copy and free first, as the output may be allocated even on error.
+        let output = unsafe { data_slice(&data) }.to_vec();
+        unsafe { WebPDataClear(&mut data) };
+        MuxError::check(code)?;
+        Ok(output)
+    }
+}
+
+impl Drop for Mux {
+    fn drop(&mut self) {
+        unsafe { WebPMuxDelete(self.mux.as_ptr()) };
+    }
+}
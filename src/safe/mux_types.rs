@@ -0,0 +1,78 @@
+use std::slice;
+
+use crate::mux_types::*;
+
+/// How a frame is treated after it has been displayed, mirroring
+/// [`WebPMuxAnimDispose`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisposeMethod {
+    /// `WEBP_MUX_DISPOSE_NONE`: leave the canvas as is.
+    #[default]
+    None,
+    /// `WEBP_MUX_DISPOSE_BACKGROUND`: clear the frame area to the background
+    /// color.
+    Background,
+}
+
+impl DisposeMethod {
+    /// Returns the raw `WebPMuxAnimDispose` value.
+    pub fn to_raw(self) -> WebPMuxAnimDispose {
+        match self {
+            DisposeMethod::None => WEBP_MUX_DISPOSE_NONE,
+            DisposeMethod::Background => WEBP_MUX_DISPOSE_BACKGROUND,
+        }
+    }
+
+    /// Converts a raw `WebPMuxAnimDispose` value. Returns `None` for unknown
+    /// values.
+    pub fn from_raw(dispose: WebPMuxAnimDispose) -> Option<Self> {
+        match dispose {
+            WEBP_MUX_DISPOSE_NONE => Some(DisposeMethod::None),
+            WEBP_MUX_DISPOSE_BACKGROUND => Some(DisposeMethod::Background),
+            _ => None,
+        }
+    }
+}
+
+/// How a frame is combined with the canvas, mirroring [`WebPMuxAnimBlend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMethod {
+    /// `WEBP_MUX_BLEND`: alpha-blend the frame onto the canvas.
+    #[default]
+    Blend,
+    /// `WEBP_MUX_NO_BLEND`: overwrite the frame area.
+    NoBlend,
+}
+
+impl BlendMethod {
+    /// Returns the raw `WebPMuxAnimBlend` value.
+    pub fn to_raw(self) -> WebPMuxAnimBlend {
+        match self {
+            BlendMethod::Blend => WEBP_MUX_BLEND,
+            BlendMethod::NoBlend => WEBP_MUX_NO_BLEND,
+        }
+    }
+
+    /// Converts a raw `WebPMuxAnimBlend` value. Returns `None` for unknown
+    /// values.
+    pub fn from_raw(blend: WebPMuxAnimBlend) -> Option<Self> {
+        match blend {
+            WEBP_MUX_BLEND => Some(BlendMethod::Blend),
+            WEBP_MUX_NO_BLEND => Some(BlendMethod::NoBlend),
+            _ => None,
+        }
+    }
+}
+
+/// Borrows the bytes described by `data`.
+///
+/// # Safety
+///
+/// `data` must be empty or point to `size` bytes valid for `'a`.
+pub(crate) unsafe fn data_slice<'a>(data: &WebPData) -> &'a [u8] {
+    if data.bytes.is_null() || data.size == 0 {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(data.bytes, data.size) }
+    }
+}
@@ -0,0 +1,269 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::*;
+use std::slice;
+
+use crate::encode::*;
+use crate::safe::encode::PixelLayout;
+use crate::safe::error::*;
+
+/// An owned [`WebPPicture`], released with `WebPPictureFree` on drop.
+///
+/// Pictures returned by [`Picture::view`] borrow the pixels of their source
+/// for `'a`; all other pictures own their memory and are `Picture<'static>`.
+#[derive(Debug)]
+pub struct Picture<'a> {
+    raw: WebPPicture,
+    _marker: PhantomData<&'a mut [u32]>,
+}
+
+// The pixel memory is either owned or exclusively borrowed, and is only
+// written through `&mut self`.
+unsafe impl Send for Picture<'_> {}
+unsafe impl Sync for Picture<'_> {}
+
+impl Picture<'static> {
+    /// Allocates a blank ARGB picture.
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let mut picture = Self::init(width, height)?;
+        if unsafe { WebPPictureAlloc(&mut picture.raw) } == 0 {
+            return Err(picture.error().into());
+        }
+        Ok(picture)
+    }
+
+    /// Imports tightly packed rows of pixels into a new ARGB picture.
+    pub fn from_pixels(
+        pixels: &[u8],
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        let stride = width as usize * layout.bytes_per_pixel();
+        Self::from_pixels_with_stride(pixels, layout, width, height, stride)
+    }
+
+    /// Imports rows of pixels that start `stride` bytes apart into a new ARGB
+    /// picture.
+    pub fn from_pixels_with_stride(
+        pixels: &[u8],
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        stride: usize,
+    ) -> Result<Self> {
+        layout.check_len(pixels.len(), width, height, stride)?;
+        let stride = c_int::try_from(stride).map_err(|_| EncodeError::BadDimension)?;
+        let mut picture = Self::init(width, height)?;
+        if unsafe { layout.import_fn()(&mut picture.raw, pixels.as_ptr(), stride) } == 0 {
+            return Err(picture.error().into());
+        }
+        Ok(picture)
+    }
+
+    fn init(width: u32, height: u32) -> Result<Self> {
+        ensure_runtime_compat()?;
+        let mut raw: WebPPicture = unsafe { mem::zeroed() };
+        if unsafe { WebPPictureInit(&mut raw) } == 0 {
+            return Err(Error::VersionMismatch);
+        }
+        raw.use_argb = 1;
+        raw.width = c_int::try_from(width).map_err(|_| EncodeError::BadDimension)?;
+        raw.height = c_int::try_from(height).map_err(|_| EncodeError::BadDimension)?;
+        Ok(Picture {
+            raw,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'a> Picture<'a> {
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.raw.width as u32
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.raw.height as u32
+    }
+
+    /// True if the samples are stored as ARGB, false for YUV(A).
+    pub fn is_argb(&self) -> bool {
+        self.raw.use_argb != 0
+    }
+
+    /// True if the picture is a view and does not own its pixels.
+    pub fn is_view(&self) -> bool {
+        unsafe { WebPPictureIsView(&self.raw) != 0 }
+    }
+
+    /// Distance in pixels between the start of two consecutive ARGB rows.
+    pub fn argb_stride(&self) -> usize {
+        self.raw.argb_stride as usize
+    }
+
+    fn argb_len(&self) -> usize {
+        match self.height().checked_sub(1) {
+            Some(rows) => rows as usize * self.argb_stride() + self.width() as usize,
+            None => 0,
+        }
+    }
+
+    /// The ARGB samples, or `None` if the picture holds YUV(A) samples.
+    pub fn argb(&self) -> Option<&[u32]> {
+        if !self.is_argb() || self.raw.argb.is_null() {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts(self.raw.argb, self.argb_len()) })
+    }
+
+    /// Mutable access to the ARGB samples.
+    pub fn argb_mut(&mut self) -> Option<&mut [u32]> {
+        if !self.is_argb() || self.raw.argb.is_null() {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts_mut(self.raw.argb, self.argb_len()) })
+    }
+
+    fn error(&self) -> EncodeError {
+        EncodeError::from_picture(&self.raw)
+    }
+
+    fn check_rect(&self, left: u32, top: u32, width: u32, height: u32) -> Result<[c_int; 4]> {
+        let fits =
+            |start: u32, len: u32, max: u32| start.checked_add(len).is_some_and(|end| end <= max);
+        if !fits(left, width, self.width()) || !fits(top, height, self.height()) {
+            return Err(EncodeError::BadDimension.into());
+        }
+        // All values are bounded by the picture dimensions, which fit in c_int.
+        Ok([left as c_int, top as c_int, width as c_int, height as c_int])
+    }
+
+    /// Crops the picture to the given rectangle, which must lie inside it.
+    /// For YUV pictures, `left` and `top` are snapped to even values.
+    pub fn crop(&mut self, left: u32, top: u32, width: u32, height: u32) -> Result<()> {
+        let [left, top, width, height] = self.check_rect(left, top, width, height)?;
+        if unsafe { WebPPictureCrop(&mut self.raw, left, top, width, height) } == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// Returns a view of the given rectangle without copying pixels.
+    ///
+    /// The view borrows `self` mutably because encoding or editing it writes
+    /// to the shared pixels.
+    pub fn view(&mut self, left: u32, top: u32, width: u32, height: u32) -> Result<Picture<'_>> {
+        let [left, top, width, height] = self.check_rect(left, top, width, height)?;
+        let mut raw: WebPPicture = unsafe { mem::zeroed() };
+        if unsafe { WebPPictureView(&self.raw, left, top, width, height, &mut raw) } == 0 {
+            return Err(EncodeError::BadDimension.into());
+        }
+        Ok(Picture {
+            raw,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Rescales the picture. If either `width` or `height` (but not both) is
+    /// 0, it is computed to preserve the aspect ratio.
+    pub fn rescale(&mut self, width: u32, height: u32) -> Result<()> {
+        let width = c_int::try_from(width).map_err(|_| EncodeError::BadDimension)?;
+        let height = c_int::try_from(height).map_err(|_| EncodeError::BadDimension)?;
+        if unsafe { WebPPictureRescale(&mut self.raw, width, height) } == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// Copies the pixels into a new picture that owns them.
+    pub fn copy(&self) -> Result<Picture<'static>> {
+        let mut raw: WebPPicture = unsafe { mem::zeroed() };
+        let ok = unsafe { WebPPictureCopy(&self.raw, &mut raw) } != 0;
+        let picture = Picture {
+            raw,
+            _marker: PhantomData,
+        };
+        if !ok {
+            return Err(EncodeError::OutOfMemory.into());
+        }
+        Ok(picture)
+    }
+
+    /// Removes transparency by blending with `background_rgb` (a 24-bit RGB
+    /// triplet). All alpha values are reset to 0xff.
+    pub fn blend_alpha(&mut self, background_rgb: u32) {
+        unsafe { WebPBlendAlpha(&mut self.raw, background_rgb) };
+    }
+
+    /// True if the picture has non fully opaque alpha values.
+    pub fn has_transparency(&self) -> bool {
+        unsafe { WebPPictureHasTransparency(&self.raw) != 0 }
+    }
+
+    /// Smoothens the samples under fully transparent areas to help
+    /// compressibility.
+    pub fn cleanup_transparent_area(&mut self) {
+        unsafe { WebPCleanupTransparentArea(&mut self.raw) };
+    }
+
+    /// Converts the ARGB samples to YUV420(A). This conversion is lossy.
+    pub fn argb_to_yuva(&mut self) -> Result<()> {
+        if unsafe { WebPPictureARGBToYUVA(&mut self.raw, WEBP_YUV420) } == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// Same as [`Picture::argb_to_yuva`], with pseudo-random dithering of
+    /// strength between 0.0 (none) and 1.0 (maximum).
+    pub fn argb_to_yuva_dithered(&mut self, dithering: f32) -> Result<()> {
+        let ok = unsafe { WebPPictureARGBToYUVADithered(&mut self.raw, WEBP_YUV420, dithering) };
+        if ok == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// Same as [`Picture::argb_to_yuva`], using the sharp (and slower)
+    /// conversion.
+    #[cfg(feature = "0_6")]
+    pub fn sharp_argb_to_yuva(&mut self) -> Result<()> {
+        if unsafe { WebPPictureSharpARGBToYUVA(&mut self.raw) } == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// Converts the YUV420(A) samples to ARGB. This conversion is lossy.
+    pub fn yuva_to_argb(&mut self) -> Result<()> {
+        if unsafe { WebPPictureYUVAToARGB(&mut self.raw) } == 0 {
+            return Err(self.error().into());
+        }
+        Ok(())
+    }
+
+    /// The underlying raw picture.
+    pub fn as_raw(&self) -> &WebPPicture {
+        &self.raw
+    }
+
+    /// Mutable access to the underlying raw picture.
+    ///
+    /// # Safety
+    ///
+    /// The pixel pointers must keep pointing to memory owned by the picture
+    /// (or borrowed for `'a`), and must not be freed.
+    pub unsafe fn as_raw_mut(&mut self) -> &mut WebPPicture {
+        &mut self.raw
+    }
+}
+
+impl Drop for Picture<'_> {
+    fn drop(&mut self) {
+        // Views own no memory unless libwebp reallocated them, in which case
+        // this releases the new buffers only.
+        unsafe { WebPPictureFree(&mut self.raw) };
+    }
+}
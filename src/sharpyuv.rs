@@ -0,0 +1,215 @@
+use std::os::raw::*;
+
+// SharpYUV API version following the convention from semver.org
+#[cfg(feature = "1_4")]
+pub const SHARPYUV_VERSION_MAJOR: c_int = 0;
+#[cfg(feature = "1_4")]
+pub const SHARPYUV_VERSION_MINOR: c_int = 4;
+#[cfg(feature = "1_4")]
+pub const SHARPYUV_VERSION_PATCH: c_int = 0;
+// Version as a uint32_t. The major number is the high 8 bits.
+// The minor number is the middle 8 bits. The patch number is the low 16 bits.
+#[cfg(feature = "1_4")]
+pub const SHARPYUV_VERSION: c_int = SHARPYUV_MAKE_VERSION(
+    SHARPYUV_VERSION_MAJOR,
+    SHARPYUV_VERSION_MINOR,
+    SHARPYUV_VERSION_PATCH,
+);
+
+#[allow(non_snake_case)]
+#[inline]
+pub const fn SHARPYUV_MAKE_VERSION(major: c_int, minor: c_int, patch: c_int) -> c_int {
+    (major << 24) | (minor << 16) | patch
+}
+
+/// A 3x4 matrix converting RGB to YUV, in 16-bit fixed point.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SharpYuvConversionMatrix {
+    pub rgb_to_y: [c_int; 4],
+    pub rgb_to_u: [c_int; 4],
+    pub rgb_to_v: [c_int; 4],
+}
+
+#[allow(non_camel_case_types)]
+pub type SharpYuvRange = u32;
+
+/// YUV values between [0;255] (for 8 bit)
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvRangeFull: SharpYuvRange = 0;
+/// Y in [16;235], YUV in [16;240] (for 8 bit)
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvRangeLimited: SharpYuvRange = 1;
+
+/// Description of a YUV color space, used to compute a conversion matrix.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SharpYuvColorSpace {
+    /// Red luma coefficient (e.g. 0.2126 for Rec. 709).
+    pub kr: f32,
+    /// Blue luma coefficient (e.g. 0.0722 for Rec. 709).
+    pub kb: f32,
+    /// 8, 10 or 12
+    pub bit_depth: c_int,
+    pub range: SharpYuvRange,
+}
+
+#[allow(non_camel_case_types)]
+pub type SharpYuvMatrixType = u32;
+
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvMatrixWebp: SharpYuvMatrixType = 0;
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvMatrixRec601Limited: SharpYuvMatrixType = 1;
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvMatrixRec601Full: SharpYuvMatrixType = 2;
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvMatrixRec709Limited: SharpYuvMatrixType = 3;
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvMatrixRec709Full: SharpYuvMatrixType = 4;
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvMatrixNum: SharpYuvMatrixType = 5;
+
+// Enums for transfer functions, as defined in H.273,
+// https://www.itu.int/rec/T-REC-H.273-202107-I/en
+#[cfg(feature = "1_4")]
+#[allow(non_camel_case_types)]
+pub type SharpYuvTransferFunctionType = u32;
+
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt709: SharpYuvTransferFunctionType = 1;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt470M: SharpYuvTransferFunctionType = 4;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt470Bg: SharpYuvTransferFunctionType = 5;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt601: SharpYuvTransferFunctionType = 6;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionSmpte240: SharpYuvTransferFunctionType = 7;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionLinear: SharpYuvTransferFunctionType = 8;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionLog100: SharpYuvTransferFunctionType = 9;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionLog100_Sqrt10: SharpYuvTransferFunctionType = 10;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionIec61966: SharpYuvTransferFunctionType = 11;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt1361: SharpYuvTransferFunctionType = 12;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionSrgb: SharpYuvTransferFunctionType = 13;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt2020_10Bit: SharpYuvTransferFunctionType = 14;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionBt2020_12Bit: SharpYuvTransferFunctionType = 15;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionSmpte2084: SharpYuvTransferFunctionType = 16;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionSmpte428: SharpYuvTransferFunctionType = 17;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionHlg: SharpYuvTransferFunctionType = 18;
+#[cfg(feature = "1_4")]
+#[allow(non_upper_case_globals)]
+pub const kSharpYuvTransferFunctionNum: SharpYuvTransferFunctionType = 19;
+
+/// Options for [`SharpYuvConvertWithOptions`].
+#[cfg(feature = "1_4")]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SharpYuvOptions {
+    /// This matrix cannot be NULL and can be initialized by
+    /// [`SharpYuvComputeConversionMatrix`].
+    pub yuv_matrix: *const SharpYuvConversionMatrix,
+    pub transfer_type: SharpYuvTransferFunctionType,
+}
+
+webp_extern! {
+    /// Returns the library's version number, packed in hexadecimal using
+    /// 8 bits for each of major/minor/patch (e.g. 0.4.0 is 0x00040000).
+    pub fn SharpYuvGetVersion() -> c_int;
+    /// Computes the RGB to YUV conversion matrix for the given color space.
+    pub fn SharpYuvComputeConversionMatrix(
+        yuv_color_space: *const SharpYuvColorSpace,
+        matrix: *mut SharpYuvConversionMatrix,
+    );
+    /// Returns a pointer to a matrix for one of the predefined color spaces.
+    pub fn SharpYuvGetConversionMatrix(
+        matrix_type: SharpYuvMatrixType,
+    ) -> *const SharpYuvConversionMatrix;
+    /// Converts RGB to YUV420 using a downsampling algorithm that minimizes
+    /// artefacts caused by chroma subsampling. Returns true on success.
+    #[must_use]
+    pub fn SharpYuvConvert(
+        r_ptr: *const c_void,
+        g_ptr: *const c_void,
+        b_ptr: *const c_void,
+        rgb_step: c_int,
+        rgb_stride: c_int,
+        rgb_bit_depth: c_int,
+        y_ptr: *mut c_void,
+        y_stride: c_int,
+        u_ptr: *mut c_void,
+        u_stride: c_int,
+        v_ptr: *mut c_void,
+        v_stride: c_int,
+        yuv_bit_depth: c_int,
+        width: c_int,
+        height: c_int,
+        yuv_matrix: *const SharpYuvConversionMatrix,
+    ) -> c_int;
+    #[cfg(feature = "1_4")]
+    #[doc(hidden)]
+    pub fn SharpYuvOptionsInitInternal(
+        yuv_matrix: *const SharpYuvConversionMatrix,
+        options: *mut SharpYuvOptions,
+        version: c_int,
+    ) -> c_int;
+    /// Same as [`SharpYuvConvert`], with a choice of transfer function.
+    #[cfg(feature = "1_4")]
+    #[must_use]
+    pub fn SharpYuvConvertWithOptions(
+        r_ptr: *const c_void,
+        g_ptr: *const c_void,
+        b_ptr: *const c_void,
+        rgb_step: c_int,
+        rgb_stride: c_int,
+        rgb_bit_depth: c_int,
+        y_ptr: *mut c_void,
+        y_stride: c_int,
+        u_ptr: *mut c_void,
+        u_stride: c_int,
+        v_ptr: *mut c_void,
+        v_stride: c_int,
+        yuv_bit_depth: c_int,
+        width: c_int,
+        height: c_int,
+        options: *const SharpYuvOptions,
+    ) -> c_int;
+}
+
+#[cfg(feature = "1_4")]
+#[allow(non_snake_case)]
+#[must_use]
+#[inline]
+pub unsafe extern "C" fn SharpYuvOptionsInit(
+    yuv_matrix: *const SharpYuvConversionMatrix,
+    options: *mut SharpYuvOptions,
+) -> c_int {
+    unsafe { SharpYuvOptionsInitInternal(yuv_matrix, options, SHARPYUV_VERSION) }
+}
@@ -0,0 +1,11 @@
+/// Returns whether libwebp runs work on separate threads, i.e. whether it was
+/// built with `WEBP_USE_THREAD`, or `None` if that is unknown.
+///
+/// Without it, `WebPDecoderOptions::use_threads`, `WebPConfig::thread_level`
+/// and `WebPAnimDecoderOptions::use_threads` are accepted but have no effect.
+/// The bundled libwebp is built with threads when the `threads` feature is
+/// enabled, except on WebAssembly. A system libwebp does not expose how it
+/// was built, so the answer is `None` when linking against one.
+pub fn threads_available() -> Option<bool> {
+    option_env!("__LIBWEBP_SYS_THREADS").map(|threads| threads == "1")
+}
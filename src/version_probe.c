@@ -0,0 +1,8 @@
+#include <stdio.h>
+#include <webp/decode.h>
+
+int main() {
+  int version = WebPGetDecoderVersion();
+  printf("%d.%d.%d\n", (version >> 16) & 0xff, (version >> 8) & 0xff, version & 0xff);
+  return 0;
+}
@@ -0,0 +1,9 @@
+#![cfg(not(feature = "runtime-loading"))]
+
+use libwebp_sys::check_runtime_compat;
+
+#[test]
+fn test_check_runtime_compat() {
+    // The build script already checked the same library.
+    check_runtime_compat().unwrap();
+}
@@ -1,3 +1,5 @@
+#![cfg(not(feature = "runtime-loading"))]
+
 use libwebp_sys::{MODE_RGB, WebPIDelete, WebPINewDecoder, WebPInitDecBuffer};
 use std::mem;
 
@@ -5,7 +7,7 @@ use std::mem;
 fn test_new_and_delete() {
     unsafe {
         let mut buf = mem::zeroed();
-        WebPInitDecBuffer(&mut buf);
+        let _ = WebPInitDecBuffer(&mut buf);
         buf.colorspace = MODE_RGB;
         buf.is_external_memory = 0;
         let idec = WebPINewDecoder(&mut buf);
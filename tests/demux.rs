@@ -1,4 +1,4 @@
-#![cfg(feature = "demux")]
+#![cfg(all(feature = "demux", not(feature = "runtime-loading")))]
 
 use libwebp_sys::{WebPData, WebPDemux, WebPDemuxDelete};
 
@@ -1,3 +1,5 @@
+#![cfg(not(feature = "runtime-loading"))]
+
 use libwebp_sys::WebPMemoryWriterInit;
 use std::mem;
 
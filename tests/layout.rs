@@ -0,0 +1,544 @@
+//! Compares the layout of the `#[repr(C)]` structs with the C headers of the
+//! libwebp being linked.
+//!
+//! A C helper printing the size and alignment of each struct, and the offset
+//! and size of every field, is generated from the Rust definitions and
+//! compiled with the compiler and include paths exported by the build script.
+//!
+//! Headers whose `*_ABI_VERSION` equals the one the enabled features declare
+//! must match exactly. Newer headers must keep the layout the features imply:
+//! the same size and alignment, and the same fields where they were, except
+//! that padding may have been taken up by new fields and fields removed
+//! upstream (declared under `cfg(not(...))`) are not looked up.
+//!
+//! The test fails if the headers cannot be compiled. Set
+//! `LIBWEBP_SYS_SKIP_LAYOUT_TEST=1` to skip it instead, e.g. when linking
+//! against a library directory without headers.
+#![cfg(not(any(
+    feature = "runtime-loading",
+    target_env = "msvc",
+    target_family = "wasm"
+)))]
+#![allow(deprecated)]
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::mem::{align_of, offset_of, size_of};
+use std::process::Command;
+
+use libwebp_sys::*;
+
+struct Layout {
+    name: &'static str,
+    size: usize,
+    align: usize,
+    fields: Vec<Field>,
+}
+
+struct Field {
+    name: &'static str,
+    offset: usize,
+    size: usize,
+    /// Declared only for releases older than an enabled feature.
+    legacy: bool,
+}
+
+impl Field {
+    fn is_padding(&self) -> bool {
+        self.name.starts_with("pad")
+    }
+}
+
+fn field_size<T, U>(_: fn(&T) -> &U) -> usize {
+    size_of::<U>()
+}
+
+/// Describes a struct. The field list must be exhaustive, which the
+/// destructuring pattern enforces.
+macro_rules! layout {
+    ($ty:ident { $($(#[$attr:meta])* $field:ident),* $(,)? }) => {{
+        #[allow(unused)]
+        fn exhaustive(value: &$ty) {
+            let $ty { $($(#[$attr])* $field: _,)* } = value;
+        }
+        #[allow(unused_mut)]
+        let mut fields = Vec::new();
+        $(
+            $(#[$attr])*
+            fields.push(Field {
+                name: stringify!($field),
+                offset: offset_of!($ty, $field),
+                size: field_size(|value: &$ty| &value.$field),
+                legacy: false $(|| stringify!($attr).starts_with("cfg(not"))*,
+            });
+        )*
+        Layout {
+            name: stringify!($ty),
+            size: size_of::<$ty>(),
+            align: align_of::<$ty>(),
+            fields,
+        }
+    }};
+}
+
+/// The structs of one header, checked exactly if `abi_macro` equals
+/// `abi_version`.
+struct Header {
+    include: &'static str,
+    abi_macro: &'static str,
+    abi_version: i32,
+    layouts: Vec<Layout>,
+}
+
+fn headers() -> Vec<Header> {
+    #[allow(unused_mut)]
+    let mut headers = vec![
+        Header {
+            include: "webp/decode.h",
+            abi_macro: "WEBP_DECODER_ABI_VERSION",
+            abi_version: WEBP_DECODER_ABI_VERSION,
+            layouts: vec![
+                layout!(WebPRGBABuffer { rgba, stride, size }),
+                layout!(WebPYUVABuffer {
+                    y,
+                    u,
+                    v,
+                    a,
+                    y_stride,
+                    u_stride,
+                    v_stride,
+                    a_stride,
+                    y_size,
+                    u_size,
+                    v_size,
+                    a_size,
+                }),
+                layout!(WebPDecBuffer {
+                    colorspace,
+                    width,
+                    height,
+                    is_external_memory,
+                    u,
+                    pad,
+                    private_memory,
+                }),
+                layout!(WebPBitstreamFeatures {
+                    width,
+                    height,
+                    has_alpha,
+                    has_animation,
+                    format,
+                    #[cfg(not(feature = "0_5"))]
+                    no_incremental_decoding,
+                    #[cfg(not(feature = "0_5"))]
+                    rotate,
+                    #[cfg(not(feature = "0_5"))]
+                    uv_sampling,
+                    pad,
+                }),
+                layout!(WebPDecoderOptions {
+                    bypass_filtering,
+                    no_fancy_upsampling,
+                    use_cropping,
+                    crop_left,
+                    crop_top,
+                    crop_width,
+                    crop_height,
+                    use_scaling,
+                    scaled_width,
+                    scaled_height,
+                    use_threads,
+                    dithering_strength,
+                    #[cfg(feature = "0_5")]
+                    flip,
+                    #[cfg(feature = "0_5")]
+                    alpha_dithering_strength,
+                    #[cfg(not(feature = "0_5"))]
+                    force_rotation,
+                    #[cfg(not(feature = "0_5"))]
+                    no_enhancement,
+                    pad,
+                }),
+                layout!(WebPDecoderConfig {
+                    input,
+                    output,
+                    options,
+                }),
+            ],
+        },
+        Header {
+            include: "webp/encode.h",
+            abi_macro: "WEBP_ENCODER_ABI_VERSION",
+            abi_version: WEBP_ENCODER_ABI_VERSION,
+            layouts: vec![
+                layout!(WebPConfig {
+                    lossless,
+                    quality,
+                    method,
+                    image_hint,
+                    target_size,
+                    target_PSNR,
+                    segments,
+                    sns_strength,
+                    filter_strength,
+                    filter_sharpness,
+                    filter_type,
+                    autofilter,
+                    alpha_compression,
+                    alpha_filtering,
+                    alpha_quality,
+                    pass,
+                    show_compressed,
+                    preprocessing,
+                    partitions,
+                    partition_limit,
+                    emulate_jpeg_size,
+                    thread_level,
+                    low_memory,
+                    #[cfg(feature = "0_5")]
+                    near_lossless,
+                    #[cfg(feature = "0_5")]
+                    exact,
+                    #[cfg(feature = "0_6")]
+                    use_delta_palette,
+                    #[cfg(feature = "0_6")]
+                    use_sharp_yuv,
+                    #[cfg(feature = "1_2")]
+                    qmin,
+                    #[cfg(feature = "1_2")]
+                    qmax,
+                    #[cfg(not(feature = "1_2"))]
+                    pad,
+                }),
+                layout!(WebPAuxStats {
+                    coded_size,
+                    PSNR,
+                    block_count,
+                    header_bytes,
+                    residual_bytes,
+                    segment_size,
+                    segment_quant,
+                    segment_level,
+                    alpha_data_size,
+                    layer_data_size,
+                    lossless_features,
+                    histogram_bits,
+                    transform_bits,
+                    cache_bits,
+                    palette_size,
+                    lossless_size,
+                    #[cfg(feature = "0_5")]
+                    lossless_hdr_size,
+                    #[cfg(feature = "0_5")]
+                    lossless_data_size,
+                    #[cfg(feature = "1_5")]
+                    cross_color_transform_bits,
+                    pad,
+                }),
+                layout!(WebPMemoryWriter {
+                    mem,
+                    size,
+                    max_size,
+                    pad,
+                }),
+                layout!(WebPPicture {
+                    use_argb,
+                    colorspace,
+                    width,
+                    height,
+                    y,
+                    u,
+                    v,
+                    y_stride,
+                    uv_stride,
+                    a,
+                    a_stride,
+                    pad1,
+                    argb,
+                    argb_stride,
+                    pad2,
+                    writer,
+                    custom_ptr,
+                    extra_info_type,
+                    extra_info,
+                    stats,
+                    error_code,
+                    progress_hook,
+                    user_data,
+                    pad3,
+                    pad4,
+                    pad5,
+                    pad6,
+                    memory_,
+                    memory_argb_,
+                    pad7,
+                }),
+            ],
+        },
+    ];
+    #[cfg(feature = "demux")]
+    headers.push(Header {
+        include: "webp/demux.h",
+        abi_macro: "WEBP_DEMUX_ABI_VERSION",
+        abi_version: WEBP_DEMUX_ABI_VERSION,
+        layouts: vec![
+            layout!(WebPData { bytes, size }),
+            layout!(WebPIterator {
+                frame_num,
+                num_frames,
+                #[cfg(not(feature = "0_5"))]
+                fragment_num,
+                #[cfg(not(feature = "0_5"))]
+                num_fragments,
+                x_offset,
+                y_offset,
+                width,
+                height,
+                duration,
+                dispose_method,
+                complete,
+                fragment,
+                has_alpha,
+                blend_method,
+                pad,
+                private_,
+            }),
+            layout!(WebPChunkIterator {
+                chunk_num,
+                num_chunks,
+                chunk,
+                pad,
+                private_,
+            }),
+            #[cfg(feature = "0_5")]
+            layout!(WebPAnimDecoderOptions {
+                color_mode,
+                use_threads,
+                padding,
+            }),
+            #[cfg(feature = "0_5")]
+            layout!(WebPAnimInfo {
+                canvas_width,
+                canvas_height,
+                loop_count,
+                bgcolor,
+                frame_count,
+                pad,
+            }),
+        ],
+    });
+    #[cfg(feature = "mux")]
+    headers.push(Header {
+        include: "webp/mux.h",
+        abi_macro: "WEBP_MUX_ABI_VERSION",
+        abi_version: WEBP_MUX_ABI_VERSION,
+        layouts: vec![
+            layout!(WebPMuxFrameInfo {
+                bitstream,
+                x_offset,
+                y_offset,
+                duration,
+                id,
+                dispose_method,
+                blend_method,
+                pad,
+            }),
+            layout!(WebPMuxAnimParams {
+                bgcolor,
+                loop_count,
+            }),
+            #[cfg(feature = "0_5")]
+            layout!(WebPAnimEncoderOptions {
+                anim_params,
+                minimize_size,
+                kmin,
+                kmax,
+                allow_mixed,
+                verbose,
+                padding,
+            }),
+        ],
+    });
+    headers
+}
+
+/// Generates a C program printing the C side of `headers` in the same form
+/// as [`rust_report`], preceded by whether each header has the ABI version
+/// the enabled features declare.
+fn c_helper(headers: &[Header]) -> String {
+    let mut c = c_includes(headers);
+    c.push_str("int main(void) {\n");
+    for header in headers {
+        writeln!(c, "#if {} == {:#06x}", header.abi_macro, header.abi_version).unwrap();
+        writeln!(c, "  printf(\"abi {} exact\\n\");", header.include).unwrap();
+        c_layouts(&mut c, header, true);
+        c.push_str("#else\n");
+        writeln!(
+            c,
+            "  printf(\"abi {} %#06x\\n\", {});",
+            header.include, header.abi_macro
+        )
+        .unwrap();
+        c_layouts(&mut c, header, false);
+        c.push_str("#endif\n");
+    }
+    c.push_str("  return 0;\n}\n");
+    c
+}
+
+fn c_layouts(c: &mut String, header: &Header, exact: bool) {
+    for layout in &header.layouts {
+        let ty = layout.name;
+        writeln!(
+            c,
+            "  printf(\"{ty} %zu %zu\\n\", sizeof({ty}), offsetof(struct {{ char c; {ty} t; }}, t));"
+        )
+        .unwrap();
+        for field in &layout.fields {
+            let name = field.name;
+            if field.legacy {
+                continue;
+            }
+            if exact || !field.is_padding() {
+                writeln!(
+                    c,
+                    "  printf(\"{ty}.{name} %zu %zu\\n\", offsetof({ty}, {name}), sizeof((({ty}*)0)->{name}));"
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    c,
+                    "  printf(\"{ty}.{name} end %zu\\n\", offsetof({ty}, {name}) + sizeof((({ty}*)0)->{name}));"
+                )
+                .unwrap();
+            }
+        }
+    }
+}
+
+fn c_includes(headers: &[Header]) -> String {
+    let mut c = String::from("#include <stddef.h>\n#include <stdio.h>\n");
+    for header in headers {
+        writeln!(c, "#include <{}>", header.include).unwrap();
+    }
+    c
+}
+
+/// The Rust side of [`c_layouts`].
+fn rust_report(header: &Header, exact: bool) -> String {
+    let mut report = String::new();
+    for layout in &header.layouts {
+        writeln!(report, "{} {} {}", layout.name, layout.size, layout.align).unwrap();
+        for field in &layout.fields {
+            if field.legacy {
+                continue;
+            }
+            if exact || !field.is_padding() {
+                writeln!(
+                    report,
+                    "{}.{} {} {}",
+                    layout.name, field.name, field.offset, field.size
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    report,
+                    "{}.{} end {}",
+                    layout.name,
+                    field.name,
+                    field.offset + field.size
+                )
+                .unwrap();
+            }
+        }
+    }
+    report
+}
+
+/// Fails with `reason`, unless `LIBWEBP_SYS_SKIP_LAYOUT_TEST=1` allows
+/// skipping the test.
+fn skip(reason: &str) {
+    if env::var_os("LIBWEBP_SYS_SKIP_LAYOUT_TEST").is_some_and(|value| value == "1") {
+        eprintln!("{reason}; skipping");
+    } else {
+        panic!("{reason}; set LIBWEBP_SYS_SKIP_LAYOUT_TEST=1 to skip this test");
+    }
+}
+
+#[test]
+fn test_layout() {
+    let (Some(compiler), Some(include_paths)) = (
+        option_env!("__LIBWEBP_SYS_CC"),
+        option_env!("__LIBWEBP_SYS_INCLUDE_PATHS"),
+    ) else {
+        return skip("the build script did not export the libwebp headers");
+    };
+    let compile = || {
+        let mut cmd = Command::new(compiler);
+        for path in env::split_paths(include_paths).filter(|path| !path.as_os_str().is_empty()) {
+            cmd.arg("-I").arg(path);
+        }
+        cmd
+    };
+
+    let headers = headers();
+    let dir = env::temp_dir().join(format!("libwebp-sys-layout-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let probe = dir.join("probe.c");
+    fs::write(&probe, c_includes(&headers)).unwrap();
+    let mut cmd = compile();
+    cmd.arg("-c").arg(&probe).arg("-o").arg(dir.join("probe.o"));
+    if !cmd.status().is_ok_and(|status| status.success()) {
+        fs::remove_dir_all(&dir).unwrap();
+        return skip(&format!(
+            "the libwebp headers are not reachable with {:?}",
+            cmd
+        ));
+    }
+
+    let source = dir.join("layout.c");
+    let exe = dir.join("layout");
+    fs::write(&source, c_helper(&headers)).unwrap();
+    let mut cmd = compile();
+    cmd.arg(&source).arg("-o").arg(&exe);
+    let status = cmd.status().unwrap();
+    assert!(
+        status.success(),
+        "failed to compile {}: {:?}",
+        source.display(),
+        cmd
+    );
+    let output = Command::new(&exe).output().unwrap();
+    assert!(output.status.success());
+    let c_report = String::from_utf8(output.stdout).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let mut expected = String::new();
+    for header in &headers {
+        let prefix = format!("abi {} ", header.include);
+        let abi = c_report
+            .lines()
+            .find_map(|line| line.strip_prefix(&prefix))
+            .unwrap();
+        expected.push_str(&rust_report(header, abi == "exact"));
+    }
+    let actual: Vec<&str> = c_report
+        .lines()
+        .filter(|line| !line.starts_with("abi "))
+        .collect();
+    let expected: Vec<&str> = expected.lines().collect();
+    assert_eq!(actual.len(), expected.len());
+    let mismatches: Vec<String> = actual
+        .iter()
+        .zip(&expected)
+        .filter(|(c, rust)| c != rust)
+        .map(|(c, rust)| format!("C: {:<48} Rust: {}", c, rust))
+        .collect();
+    assert!(
+        mismatches.is_empty(),
+        "layout mismatches (name, size or offset, and size or alignment):\n{}",
+        mismatches.join("\n")
+    );
+}
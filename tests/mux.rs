@@ -1,4 +1,4 @@
-#![cfg(feature = "mux")]
+#![cfg(all(feature = "mux", not(feature = "runtime-loading")))]
 
 use libwebp_sys::{WebPMuxDelete, WebPMuxNew};
 
@@ -0,0 +1,71 @@
+//! The `prefix-symbols` build renames every global the bundled libraries
+//! define, not only the API, as any of them could clash with another libwebp
+//! linked into the same binary.
+#![cfg(feature = "prefix-symbols")]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const PREFIX: &str = env!("__LIBWEBP_SYS_SYMBOL_PREFIX");
+
+#[test]
+fn test_only_prefixed_globals() {
+    let lib_dir = Path::new(env!("__LIBWEBP_SYS_LIB_DIR"));
+    let mut archives = Vec::new();
+    for entry in fs::read_dir(lib_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == "a" || ext == "lib")
+        {
+            archives.push(path);
+        }
+    }
+    assert!(
+        archives.iter().any(|path| {
+            let name = path.file_name().unwrap();
+            name == "libwebp.a" || name == "webp.lib"
+        }),
+        "no libwebp in {}",
+        lib_dir.display()
+    );
+    let output = Command::new(env!("__LIBWEBP_SYS_NM"))
+        .arg("-g")
+        .arg("-P")
+        .args(&archives)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // C names get a leading underscore on Mach-O and 32-bit Windows.
+    let underscore = cfg!(target_vendor = "apple") || cfg!(all(windows, target_arch = "x86"));
+    let mut unprefixed = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(kind)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if matches!(kind, "U" | "v" | "w") {
+            continue;
+        }
+        let name = match name.strip_prefix('_') {
+            Some(name) if underscore => name,
+            _ => name,
+        };
+        // The compiler's own symbols, such as `__x86.get_pc_thunk.bx`, are
+        // left alone.
+        let is_c_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_c_identifier && !name.starts_with(PREFIX) {
+            unprefixed.push(name.to_owned());
+        }
+    }
+    assert!(
+        unprefixed.is_empty(),
+        "globals without the {PREFIX:?} prefix: {unprefixed:?}"
+    );
+}
@@ -0,0 +1,77 @@
+#![cfg(all(feature = "runtime-loading", unix))]
+
+use libwebp_sys::runtime::{Library, LibraryPaths, LoadError};
+
+const WEBP_IMAGE: [u8; 94] = [
+    0x52, 0x49, 0x46, 0x46, 0x56, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38, 0x20,
+    0x4A, 0x00, 0x00, 0x00, 0xD0, 0x01, 0x00, 0x9D, 0x01, 0x2A, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00,
+    0x34, 0x25, 0xA8, 0x02, 0x74, 0x01, 0x0E, 0xFE, 0x03, 0x8E, 0x00, 0x00, 0xFE, 0xAD, 0xFF, 0xF1,
+    0x5C, 0xB4, 0xF8, 0xED, 0xFF, 0xF0, 0xC0, 0xBA, 0xBF, 0x93, 0x05, 0xEA, 0x0C, 0x9F, 0x93, 0x3F,
+    0xE8, 0xC0, 0xBF, 0x3F, 0xFF, 0xA9, 0xBF, 0xFF, 0x24, 0x7B, 0xCB, 0xFF, 0x46, 0x05, 0xF9, 0xFF,
+    0xFD, 0x4D, 0xFE, 0x30, 0xE5, 0x86, 0xAA, 0x07, 0x31, 0x23, 0x6F, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn test_decode() {
+    let library = Library::global().unwrap();
+    let webp = library.webp();
+    unsafe {
+        let (mut width, mut height) = (0, 0);
+        let ok = (webp.WebPGetInfo)(
+            WEBP_IMAGE.as_ptr(),
+            WEBP_IMAGE.len(),
+            &mut width,
+            &mut height,
+        );
+        assert!(ok != 0);
+        assert_eq!((width, height), (3, 2));
+
+        let mut rgba = vec![0_u8; (width * height * 4) as usize];
+        let output = (webp.WebPDecodeRGBAInto)(
+            WEBP_IMAGE.as_ptr(),
+            WEBP_IMAGE.len(),
+            rgba.as_mut_ptr(),
+            rgba.len(),
+            width * 4,
+        );
+        assert_eq!(output, rgba.as_mut_ptr());
+    }
+}
+
+#[test]
+fn test_has_symbol() {
+    let library = Library::global().unwrap();
+    assert!(library.has_symbol("WebPGetDecoderVersion"));
+    assert!(!library.has_symbol("WebPNoSuchFunction"));
+}
+
+#[test]
+#[cfg(feature = "demux")]
+fn test_demux() {
+    use std::ptr;
+
+    use libwebp_sys::{WEBP_DEMUX_ABI_VERSION, WEBP_FF_CANVAS_WIDTH, WebPData};
+
+    let Some(demux) = Library::global().unwrap().demux() else {
+        return;
+    };
+    unsafe {
+        let data = WebPData {
+            bytes: WEBP_IMAGE.as_ptr(),
+            size: WEBP_IMAGE.len(),
+        };
+        let dmux = (demux.WebPDemuxInternal)(&data, 0, ptr::null_mut(), WEBP_DEMUX_ABI_VERSION);
+        assert!(!dmux.is_null());
+        assert_eq!((demux.WebPDemuxGetI)(dmux, WEBP_FF_CANVAS_WIDTH), 3);
+        (demux.WebPDemuxDelete)(dmux);
+    }
+}
+
+#[test]
+fn test_missing_library() {
+    let paths = LibraryPaths::new().webp("libwebp-sys2-nonexistent.so");
+    match Library::load_with(&paths) {
+        Err(LoadError::Open { library, .. }) => assert_eq!(library, "libwebp-sys2-nonexistent.so"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
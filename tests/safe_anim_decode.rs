@@ -0,0 +1,53 @@
+#![cfg(all(feature = "safe", feature = "demux", feature = "0_5"))]
+
+use libwebp_sys::safe::{AnimDecoder, AnimDecoderOptions, ColorMode, DecodeError, Error};
+
+fn animated() -> Vec<u8> {
+    std::fs::read("./tests/animated.webp").unwrap()
+}
+
+#[test]
+fn test_frames() {
+    let data = animated();
+    let mut decoder = AnimDecoder::new(&data).unwrap();
+    let info = *decoder.info();
+    assert_eq!(info.loop_count, 0);
+    assert_eq!(info.frame_count, 10);
+    assert_eq!((info.canvas_width, info.canvas_height), (400, 400));
+
+    let frames = decoder.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(frames.len(), 10);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.timestamp_ms(), 40 * (i as i32 + 1));
+        assert_eq!((frame.width(), frame.height()), (400, 400));
+        assert_eq!(frame.pixels().len(), 400 * 400 * 4);
+    }
+    assert!(!decoder.has_more_frames());
+    assert!(decoder.next().is_none());
+
+    decoder.reset();
+    let first = decoder.next().unwrap().unwrap();
+    assert_eq!(first.timestamp_ms(), 40);
+    assert_eq!(first.pixels(), frames[0].pixels());
+}
+
+#[test]
+fn test_options() {
+    let data = animated();
+    let options = AnimDecoderOptions::new().color_mode(ColorMode::Bgra);
+    let mut decoder = AnimDecoder::with_options(&data, &options).unwrap();
+    assert_eq!(
+        decoder.next().unwrap().unwrap().color_mode(),
+        ColorMode::Bgra
+    );
+
+    let options = AnimDecoderOptions::new().color_mode(ColorMode::Rgb);
+    assert!(matches!(
+        AnimDecoder::with_options(&data, &options),
+        Err(Error::Decode(DecodeError::InvalidParam))
+    ));
+    assert!(matches!(
+        AnimDecoder::new(b"RIFF\x04\x00\x00\x00WEBP"),
+        Err(Error::Decode(DecodeError::BitstreamError))
+    ));
+}
@@ -0,0 +1,55 @@
+#![cfg(all(feature = "safe", feature = "mux", feature = "0_5"))]
+
+use libwebp_sys::safe::{AnimEncoder, AnimEncoderOptions, Config, Error, Picture, PixelLayout};
+
+fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Vec<u8> {
+    rgba.repeat((width * height) as usize)
+}
+
+#[test]
+fn test_encode_frames() {
+    let options = AnimEncoderOptions::new().loop_count(3).allow_mixed(true);
+    let mut encoder = AnimEncoder::new(16, 8, &options).unwrap();
+    let config = Config::new_lossless();
+    encoder
+        .add_rgba(&solid(16, 8, [255, 0, 0, 255]), 0, &config)
+        .unwrap();
+    let mut picture =
+        Picture::from_pixels(&solid(16, 8, [0, 0, 255, 128]), PixelLayout::Rgba, 16, 8).unwrap();
+    encoder.add_frame(&mut picture, 100, &config).unwrap();
+    let webp = encoder.finish(250).unwrap();
+    assert_eq!(&webp[..4], b"RIFF");
+    assert_eq!(&webp[8..12], b"WEBP");
+
+    #[cfg(feature = "demux")]
+    {
+        use libwebp_sys::safe::AnimDecoder;
+
+        let decoder = AnimDecoder::new(&webp).unwrap();
+        assert_eq!(decoder.info().loop_count, 3);
+        assert_eq!(decoder.info().frame_count, 2);
+        let timestamps = decoder
+            .map(|frame| frame.unwrap().timestamp_ms())
+            .collect::<Vec<_>>();
+        assert_eq!(timestamps, [100, 250]);
+    }
+}
+
+#[test]
+fn test_errors() {
+    assert!(AnimEncoder::new(0, 8, &AnimEncoderOptions::new()).is_err());
+
+    let mut encoder = AnimEncoder::new(16, 8, &AnimEncoderOptions::new()).unwrap();
+    let config = Config::new();
+    assert!(matches!(
+        encoder.add_rgba(&[0; 16], 0, &config),
+        Err(Error::BufferTooSmall { .. })
+    ));
+    encoder
+        .add_rgba(&solid(16, 8, [0, 255, 0, 255]), 100, &config)
+        .unwrap();
+    match encoder.add_rgba(&solid(16, 8, [0, 0, 0, 255]), 50, &config) {
+        Err(Error::AnimEncode(e)) => assert!(!e.message().is_empty()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "safe")]
+
+use libwebp_sys::safe::{ColorMode, DecodeError, Decoder, Error};
+
+const WEBP_IMAGE: [u8; 94] = [
+    0x52, 0x49, 0x46, 0x46, 0x56, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38, 0x20,
+    0x4A, 0x00, 0x00, 0x00, 0xD0, 0x01, 0x00, 0x9D, 0x01, 0x2A, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00,
+    0x34, 0x25, 0xA8, 0x02, 0x74, 0x01, 0x0E, 0xFE, 0x03, 0x8E, 0x00, 0x00, 0xFE, 0xAD, 0xFF, 0xF1,
+    0x5C, 0xB4, 0xF8, 0xED, 0xFF, 0xF0, 0xC0, 0xBA, 0xBF, 0x93, 0x05, 0xEA, 0x0C, 0x9F, 0x93, 0x3F,
+    0xE8, 0xC0, 0xBF, 0x3F, 0xFF, 0xA9, 0xBF, 0xFF, 0x24, 0x7B, 0xCB, 0xFF, 0x46, 0x05, 0xF9, 0xFF,
+    0xFD, 0x4D, 0xFE, 0x30, 0xE5, 0x86, 0xAA, 0x07, 0x31, 0x23, 0x6F, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn test_decode_rgba() {
+    let image = Decoder::new().decode(&WEBP_IMAGE).unwrap();
+    assert_eq!((image.width(), image.height()), (3, 2));
+    assert_eq!(image.color_mode(), ColorMode::Rgba);
+    assert!(image.yuva().is_none());
+    let plane = image.rgb().unwrap();
+    assert!(plane.stride() >= 3 * 4);
+    assert_eq!(plane.rows().count(), 2);
+    assert!(plane.rows().all(|row| row.len() == 3 * 4));
+}
+
+#[test]
+fn test_decode_yuv_scaled() {
+    let image = Decoder::new()
+        .color_mode(ColorMode::Yuv)
+        .scale(5, 4)
+        .decode(&WEBP_IMAGE)
+        .unwrap();
+    assert_eq!((image.width(), image.height()), (5, 4));
+    assert!(image.rgb().is_none());
+    let planes = image.yuva().unwrap();
+    assert_eq!(planes.y.rows().count(), 4);
+    assert_eq!(planes.u.row(0).unwrap().len(), 3);
+    assert_eq!(planes.v.rows().count(), 2);
+    assert!(planes.a.is_none());
+}
+
+#[test]
+fn test_decode_error() {
+    match Decoder::new().decode(&WEBP_IMAGE[..40]) {
+        Err(Error::Decode(DecodeError::NotEnoughData)) => {}
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
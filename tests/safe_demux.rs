@@ -0,0 +1,79 @@
+#![cfg(all(feature = "safe", feature = "demux"))]
+
+use libwebp_sys::safe::{BlendMethod, DecodeError, Decoder, Demuxer, DisposeMethod, Error};
+use libwebp_sys::{ALPHA_FLAG, ANIMATION_FLAG};
+
+const WEBP_IMAGE: [u8; 94] = [
+    0x52, 0x49, 0x46, 0x46, 0x56, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38, 0x20,
+    0x4A, 0x00, 0x00, 0x00, 0xD0, 0x01, 0x00, 0x9D, 0x01, 0x2A, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00,
+    0x34, 0x25, 0xA8, 0x02, 0x74, 0x01, 0x0E, 0xFE, 0x03, 0x8E, 0x00, 0x00, 0xFE, 0xAD, 0xFF, 0xF1,
+    0x5C, 0xB4, 0xF8, 0xED, 0xFF, 0xF0, 0xC0, 0xBA, 0xBF, 0x93, 0x05, 0xEA, 0x0C, 0x9F, 0x93, 0x3F,
+    0xE8, 0xC0, 0xBF, 0x3F, 0xFF, 0xA9, 0xBF, 0xFF, 0x24, 0x7B, 0xCB, 0xFF, 0x46, 0x05, 0xF9, 0xFF,
+    0xFD, 0x4D, 0xFE, 0x30, 0xE5, 0x86, 0xAA, 0x07, 0x31, 0x23, 0x6F, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn test_still_image() {
+    let demuxer = Demuxer::new(&WEBP_IMAGE).unwrap();
+    assert_eq!((demuxer.canvas_width(), demuxer.canvas_height()), (3, 2));
+    assert_eq!(demuxer.frame_count(), 1);
+    assert_eq!(demuxer.format_flags() & (ANIMATION_FLAG | ALPHA_FLAG), 0);
+
+    let frames = demuxer.frames().collect::<Vec<_>>();
+    assert_eq!(frames.len(), 1);
+    assert_eq!((frames[0].width(), frames[0].height()), (3, 2));
+    assert!(frames[0].is_complete());
+    assert!(WEBP_IMAGE.ends_with(frames[0].data()));
+    assert!(demuxer.chunks(b"EXIF").next().is_none());
+
+    let image = Decoder::new().decode(frames[0].data()).unwrap();
+    assert_eq!((image.width(), image.height()), (3, 2));
+}
+
+#[test]
+fn test_animation() {
+    let data = std::fs::read("./tests/animated.webp").unwrap();
+    let demuxer = Demuxer::new(&data).unwrap();
+    assert_ne!(demuxer.format_flags() & ANIMATION_FLAG, 0);
+    assert_eq!(
+        (demuxer.canvas_width(), demuxer.canvas_height()),
+        (400, 400)
+    );
+    assert_eq!(demuxer.loop_count(), 0);
+    assert_eq!(demuxer.frame_count(), 10);
+
+    let frames = demuxer.frames().collect::<Vec<_>>();
+    assert_eq!(frames.len(), 10);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.frame_number(), i as u32 + 1);
+        assert_eq!(frame.duration(), 40);
+        assert!(!frame.data().is_empty());
+        assert!(matches!(
+            frame.dispose_method(),
+            DisposeMethod::None | DisposeMethod::Background
+        ));
+        assert!(matches!(
+            frame.blend_method(),
+            BlendMethod::Blend | BlendMethod::NoBlend
+        ));
+    }
+    assert_eq!(demuxer.frame(0).unwrap().frame_number(), 10);
+    assert!(demuxer.frame(11).is_none());
+
+    // Payloads outlive the demuxer.
+    let first = demuxer.frame(1).unwrap().data();
+    drop(demuxer);
+    assert_eq!(first, frames[0].data());
+}
+
+#[test]
+fn test_errors() {
+    assert!(matches!(
+        Demuxer::new(&WEBP_IMAGE[..40]),
+        Err(Error::Decode(DecodeError::NotEnoughData))
+    ));
+    assert!(matches!(
+        Demuxer::new(b"RIFF\x04\x00\x00\x00WEBPJUNK"),
+        Err(Error::Decode(_))
+    ));
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "safe")]
+
+use libwebp_sys::safe::{Config, Decoder, EncodeError, Encoder, Error, PixelLayout};
+
+fn gradient(width: u32, height: u32) -> Vec<u8> {
+    let mut rgba = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            rgba.extend_from_slice(&[(x * 16) as u8, (y * 16) as u8, 128, 255]);
+        }
+    }
+    rgba
+}
+
+#[test]
+fn test_encode_lossless_roundtrip() {
+    let rgba = gradient(16, 8);
+    let webp = Encoder::new(Config::new_lossless())
+        .encode(&rgba, PixelLayout::Rgba, 16, 8)
+        .unwrap();
+    let image = Decoder::new().decode(&webp).unwrap();
+    assert_eq!((image.width(), image.height()), (16, 8));
+    let decoded: Vec<u8> = image.rgb().unwrap().rows().flatten().copied().collect();
+    assert_eq!(decoded, rgba);
+}
+
+#[test]
+fn test_encode_lossy_with_stride() {
+    let rgba = gradient(16, 8);
+    let webp = Encoder::new(Config::new().quality(50.0).method(6))
+        .encode_with_stride(&rgba, PixelLayout::Rgbx, 8, 8, 16 * 4)
+        .unwrap();
+    let image = Decoder::new().decode(&webp).unwrap();
+    assert_eq!((image.width(), image.height()), (8, 8));
+}
+
+#[test]
+fn test_encode_errors() {
+    let rgba = gradient(4, 4);
+    match Encoder::default().encode(&rgba, PixelLayout::Rgba, 4, 5) {
+        Err(Error::BufferTooSmall { expected, actual }) => {
+            assert_eq!((expected, actual), (80, 64));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    match Encoder::new(Config::new().method(7)).encode(&rgba, PixelLayout::Rgba, 4, 4) {
+        Err(Error::Encode(EncodeError::InvalidConfiguration)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+    match Encoder::default().encode(&[], PixelLayout::Rgba, 0, 0) {
+        Err(Error::Encode(EncodeError::BadDimension)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+struct FailingWriter {
+    remaining: usize,
+}
+
+impl std::io::Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            return Err(std::io::Error::other("disk full"));
+        }
+        let n = buf.len().min(self.remaining);
+        self.remaining -= n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_encode_to_writer() {
+    let rgba = gradient(16, 8);
+    let encoder = Encoder::new(Config::new_lossless());
+    let mut streamed = Vec::new();
+    encoder
+        .encode_to_writer(&rgba, PixelLayout::Rgba, 16, 8, &mut streamed)
+        .unwrap();
+    let buffered = encoder.encode(&rgba, PixelLayout::Rgba, 16, 8).unwrap();
+    assert_eq!(streamed, buffered);
+
+    let writer = FailingWriter { remaining: 10 };
+    match encoder.encode_to_writer(&rgba, PixelLayout::Rgba, 16, 8, writer) {
+        Err(Error::Io(e)) => assert_eq!(e.to_string(), "disk full"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
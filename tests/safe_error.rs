@@ -0,0 +1,52 @@
+#![cfg(feature = "safe")]
+
+use libwebp_sys::safe::{DecodeError, EncodeError, Error};
+use libwebp_sys::{
+    VP8_ENC_ERROR_BAD_DIMENSION, VP8_ENC_ERROR_LAST, VP8_ENC_OK, VP8_STATUS_NOT_ENOUGH_DATA,
+    VP8_STATUS_OK, VP8StatusCode, WebPEncodingError,
+};
+
+#[test]
+fn test_decode_error_conversions() {
+    assert_eq!(DecodeError::try_from(VP8_STATUS_OK), Err(VP8_STATUS_OK));
+    assert_eq!(DecodeError::try_from(42), Err(42));
+    let e = DecodeError::try_from(VP8_STATUS_NOT_ENOUGH_DATA).unwrap();
+    assert_eq!(e, DecodeError::NotEnoughData);
+    assert_eq!(VP8StatusCode::from(e), VP8_STATUS_NOT_ENOUGH_DATA);
+}
+
+#[test]
+fn test_encode_error_conversions() {
+    assert_eq!(EncodeError::try_from(VP8_ENC_OK), Err(VP8_ENC_OK));
+    assert_eq!(
+        EncodeError::try_from(VP8_ENC_ERROR_LAST),
+        Err(VP8_ENC_ERROR_LAST)
+    );
+    let e = EncodeError::try_from(VP8_ENC_ERROR_BAD_DIMENSION).unwrap();
+    assert_eq!(WebPEncodingError::from(e), VP8_ENC_ERROR_BAD_DIMENSION);
+    assert_eq!(e.to_string(), "picture has invalid width/height");
+    let e: Error = e.into();
+    assert!(std::error::Error::source(&e).is_some());
+}
+
+#[cfg(feature = "mux")]
+#[test]
+fn test_mux_error_conversions() {
+    use libwebp_sys::safe::MuxError;
+    use libwebp_sys::{WEBP_MUX_BAD_DATA, WEBP_MUX_OK, WebPMuxError};
+
+    assert_eq!(MuxError::try_from(WEBP_MUX_OK), Err(WEBP_MUX_OK));
+    let e = MuxError::try_from(WEBP_MUX_BAD_DATA).unwrap();
+    assert_eq!(e, MuxError::BadData);
+    assert_eq!(WebPMuxError::from(e), WEBP_MUX_BAD_DATA);
+}
+
+#[cfg(feature = "runtime-check")]
+#[test]
+fn test_runtime_check() {
+    use libwebp_sys::safe::Picture;
+
+    // The check runs before the first call and passes against the library
+    // the build script accepted.
+    Picture::new(1, 1).unwrap();
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "safe")]
+
+use libwebp_sys::safe::{
+    ColorMode, Config, DecodeError, Decoder, Encoder, Error, IncrementalDecoder, IncrementalStatus,
+    PixelLayout,
+};
+
+fn encoded_image() -> Vec<u8> {
+    let mut rgb = Vec::new();
+    for y in 0..64_u32 {
+        for x in 0..48_u32 {
+            rgb.extend_from_slice(&[(x * 5) as u8, (y * 4) as u8, ((x + y) * 2) as u8]);
+        }
+    }
+    Encoder::new(Config::new().quality(90.0))
+        .encode(&rgb, PixelLayout::Rgb, 48, 64)
+        .unwrap()
+}
+
+#[test]
+fn test_append() {
+    let webp = encoded_image();
+    let mut idec = IncrementalDecoder::new(ColorMode::Rgb).unwrap();
+    assert!(idec.decoded().is_none());
+    let mut last_rows = 0;
+    for chunk in webp.chunks(64) {
+        let status = idec.append(chunk).unwrap();
+        if let Some(partial) = idec.decoded() {
+            assert_eq!((partial.width(), partial.height()), (48, 64));
+            assert!(partial.decoded_rows() >= last_rows);
+            last_rows = partial.decoded_rows();
+            assert_eq!(partial.rgb().unwrap().rows().count(), last_rows as usize);
+        }
+        if status == IncrementalStatus::Complete {
+            break;
+        }
+    }
+    assert_eq!(idec.status(), IncrementalStatus::Complete);
+    assert_eq!(last_rows, 64);
+
+    let image = idec.finish().unwrap();
+    let expected = Decoder::new()
+        .color_mode(ColorMode::Rgb)
+        .decode(&webp)
+        .unwrap();
+    let rows: Vec<&[u8]> = image.rgb().unwrap().rows().collect();
+    let expected_rows: Vec<&[u8]> = expected.rgb().unwrap().rows().collect();
+    assert_eq!(rows, expected_rows);
+}
+
+#[test]
+fn test_update_with_options() {
+    let webp = encoded_image();
+    let decoder = Decoder::new().color_mode(ColorMode::Yuv).scale(24, 32);
+    let mut idec = IncrementalDecoder::with_options(&decoder).unwrap();
+    let mut received = Vec::new();
+    for chunk in webp.chunks(100) {
+        received.extend_from_slice(chunk);
+        idec.update(&received).unwrap();
+    }
+    let image = idec.finish().unwrap();
+    assert_eq!((image.width(), image.height()), (24, 32));
+    assert_eq!(image.yuva().unwrap().u.rows().count(), 16);
+}
+
+#[cfg(feature = "0_5")]
+#[test]
+fn test_flip() {
+    let webp = encoded_image();
+    let decoder = Decoder::new().color_mode(ColorMode::Rgb).flip(true);
+    let mut idec = IncrementalDecoder::with_options(&decoder).unwrap();
+    idec.append(&webp[..webp.len() / 2]).unwrap();
+    assert!(idec.decoded().is_none());
+    idec.append(&webp[webp.len() / 2..]).unwrap();
+    assert!(idec.decoded().is_none());
+
+    let image = idec.finish().unwrap();
+    let expected = Decoder::new()
+        .color_mode(ColorMode::Rgb)
+        .decode(&webp)
+        .unwrap();
+    let rows: Vec<&[u8]> = image.rgb().unwrap().rows().collect();
+    let mut expected_rows: Vec<&[u8]> = expected.rgb().unwrap().rows().collect();
+    expected_rows.reverse();
+    assert_eq!(rows, expected_rows);
+}
+
+#[test]
+fn test_truncated() {
+    let webp = encoded_image();
+    let mut idec = IncrementalDecoder::new(ColorMode::Rgba).unwrap();
+    let status = idec.append(&webp[..webp.len() / 2]).unwrap();
+    assert_eq!(status, IncrementalStatus::NeedMoreData);
+    match idec.finish() {
+        Err(Error::Decode(DecodeError::NotEnoughData)) => {}
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    let mut idec = IncrementalDecoder::new(ColorMode::Rgba).unwrap();
+    assert!(
+        idec.append(b"RIFF\x10\x00\x00\x00WEBPJUNKJUNKJUNK")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_read_from() {
+    let webp = encoded_image();
+    let image = IncrementalDecoder::new(ColorMode::Rgba)
+        .unwrap()
+        .read_from(webp.as_slice())
+        .unwrap();
+    assert_eq!((image.width(), image.height()), (48, 64));
+
+    let truncated = &webp[..webp.len() * 3 / 4];
+    let err = IncrementalDecoder::new(ColorMode::Rgba)
+        .unwrap()
+        .read_from(truncated)
+        .unwrap_err();
+    assert!(matches!(
+        err.error(),
+        Error::Decode(DecodeError::NotEnoughData)
+    ));
+    let partial = err.partial().unwrap();
+    assert!(partial.decoded_rows() > 0 && partial.decoded_rows() < 64);
+
+    let mut idec = err.into_decoder();
+    idec.append(&webp[truncated.len()..]).unwrap();
+    assert_eq!(idec.finish().unwrap().height(), 64);
+}
@@ -0,0 +1,66 @@
+#![cfg(all(feature = "safe", feature = "mux", feature = "demux"))]
+
+use libwebp_sys::safe::Demuxer;
+use libwebp_sys::safe::metadata::{self, Metadata, MetadataKind};
+use libwebp_sys::{ANIMATION_FLAG, EXIF_FLAG, ICCP_FLAG, XMP_FLAG};
+
+const WEBP_IMAGE: [u8; 94] = [
+    0x52, 0x49, 0x46, 0x46, 0x56, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38, 0x20,
+    0x4A, 0x00, 0x00, 0x00, 0xD0, 0x01, 0x00, 0x9D, 0x01, 0x2A, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00,
+    0x34, 0x25, 0xA8, 0x02, 0x74, 0x01, 0x0E, 0xFE, 0x03, 0x8E, 0x00, 0x00, 0xFE, 0xAD, 0xFF, 0xF1,
+    0x5C, 0xB4, 0xF8, 0xED, 0xFF, 0xF0, 0xC0, 0xBA, 0xBF, 0x93, 0x05, 0xEA, 0x0C, 0x9F, 0x93, 0x3F,
+    0xE8, 0xC0, 0xBF, 0x3F, 0xFF, 0xA9, 0xBF, 0xFF, 0x24, 0x7B, 0xCB, 0xFF, 0x46, 0x05, 0xF9, 0xFF,
+    0xFD, 0x4D, 0xFE, 0x30, 0xE5, 0x86, 0xAA, 0x07, 0x31, 0x23, 0x6F, 0x00, 0x00, 0x00,
+];
+
+fn sample() -> Metadata {
+    Metadata {
+        exif: Some(b"Exif\0\0MM".to_vec()),
+        icc_profile: Some(b"icc profile".to_vec()),
+        xmp: Some(b"<x:xmpmeta/>".to_vec()),
+    }
+}
+
+fn flags(data: &[u8]) -> u32 {
+    Demuxer::new(data).unwrap().format_flags()
+}
+
+#[test]
+fn test_still_image() {
+    assert!(metadata::read(&WEBP_IMAGE).unwrap().is_empty());
+
+    let tagged = metadata::embed(&WEBP_IMAGE, &sample()).unwrap();
+    assert_eq!(metadata::read(&tagged).unwrap(), sample());
+    assert_eq!(
+        flags(&tagged) & (EXIF_FLAG | ICCP_FLAG | XMP_FLAG),
+        EXIF_FLAG | ICCP_FLAG | XMP_FLAG
+    );
+
+    let partial = metadata::remove(&tagged, &[MetadataKind::Exif]).unwrap();
+    let read = metadata::read(&partial).unwrap();
+    assert_eq!(read.exif, None);
+    assert_eq!(read.get(MetadataKind::Xmp), sample().get(MetadataKind::Xmp));
+    assert_eq!(flags(&partial) & EXIF_FLAG, 0);
+
+    let stripped = metadata::strip(&tagged).unwrap();
+    assert!(metadata::read(&stripped).unwrap().is_empty());
+    // Without metadata the simple file format is used again.
+    assert_eq!(&stripped[12..16], b"VP8 ");
+}
+
+#[test]
+fn test_animation() {
+    let data = std::fs::read("./tests/animated.webp").unwrap();
+    let update = Metadata {
+        xmp: Some(b"<x:xmpmeta/>".to_vec()),
+        ..Metadata::default()
+    };
+    let tagged = metadata::embed(&data, &update).unwrap();
+    assert_eq!(metadata::read(&tagged).unwrap().xmp, update.xmp);
+    assert_ne!(flags(&tagged) & ANIMATION_FLAG, 0);
+    assert_ne!(flags(&tagged) & XMP_FLAG, 0);
+
+    let stripped = metadata::strip(&tagged).unwrap();
+    assert!(metadata::read(&stripped).unwrap().is_empty());
+    assert_eq!(Demuxer::new(&stripped).unwrap().frame_count(), 10);
+}
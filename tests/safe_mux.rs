@@ -0,0 +1,91 @@
+#![cfg(all(feature = "safe", feature = "mux"))]
+
+use libwebp_sys::safe::{AnimParams, BlendMethod, DisposeMethod, Error, FrameInfo, Mux, MuxError};
+use libwebp_sys::{ANIMATION_FLAG, ICCP_FLAG};
+
+const WEBP_IMAGE: [u8; 94] = [
+    0x52, 0x49, 0x46, 0x46, 0x56, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38, 0x20,
+    0x4A, 0x00, 0x00, 0x00, 0xD0, 0x01, 0x00, 0x9D, 0x01, 0x2A, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00,
+    0x34, 0x25, 0xA8, 0x02, 0x74, 0x01, 0x0E, 0xFE, 0x03, 0x8E, 0x00, 0x00, 0xFE, 0xAD, 0xFF, 0xF1,
+    0x5C, 0xB4, 0xF8, 0xED, 0xFF, 0xF0, 0xC0, 0xBA, 0xBF, 0x93, 0x05, 0xEA, 0x0C, 0x9F, 0x93, 0x3F,
+    0xE8, 0xC0, 0xBF, 0x3F, 0xFF, 0xA9, 0xBF, 0xFF, 0x24, 0x7B, 0xCB, 0xFF, 0x46, 0x05, 0xF9, 0xFF,
+    0xFD, 0x4D, 0xFE, 0x30, 0xE5, 0x86, 0xAA, 0x07, 0x31, 0x23, 0x6F, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn test_chunks() {
+    let mut mux = Mux::from_bytes(&WEBP_IMAGE).unwrap();
+    assert_eq!(mux.canvas_size().unwrap(), (3, 2));
+    assert_eq!(mux.chunk(b"ICCP").unwrap(), None);
+    assert!(!mux.delete_chunk(b"ICCP").unwrap());
+
+    mux.set_chunk(b"ICCP", b"profile").unwrap();
+    assert_eq!(mux.chunk(b"ICCP").unwrap(), Some(&b"profile"[..]));
+    assert_ne!(mux.features().unwrap() & ICCP_FLAG, 0);
+    let webp = mux.assemble().unwrap();
+    assert_eq!(&webp[12..16], b"VP8X");
+
+    let mut mux = Mux::from_bytes(&webp).unwrap();
+    assert_eq!(mux.chunk(b"ICCP").unwrap(), Some(&b"profile"[..]));
+    assert!(mux.delete_chunk(b"ICCP").unwrap());
+    let webp = mux.assemble().unwrap();
+    let mux = Mux::from_bytes(&webp).unwrap();
+    assert_eq!(mux.features().unwrap() & ICCP_FLAG, 0);
+
+    let frame = mux.frame(1).unwrap().unwrap();
+    assert!(WEBP_IMAGE.ends_with(&frame.bitstream));
+    assert_eq!(frame.info, FrameInfo::default());
+}
+
+#[test]
+fn test_frames() {
+    let bitstream = Mux::from_bytes(&WEBP_IMAGE)
+        .unwrap()
+        .frame(1)
+        .unwrap()
+        .unwrap()
+        .bitstream;
+
+    let mut mux = Mux::new().unwrap();
+    let info = FrameInfo {
+        x_offset: 2,
+        y_offset: 4,
+        duration: 100,
+        dispose_method: DisposeMethod::Background,
+        blend_method: BlendMethod::NoBlend,
+    };
+    mux.push_frame(&bitstream, &info).unwrap();
+    mux.push_frame(&bitstream, &FrameInfo::default()).unwrap();
+    let params = AnimParams {
+        bgcolor: 0x11223344,
+        loop_count: 5,
+    };
+    mux.set_animation_params(&params).unwrap();
+    #[cfg(feature = "0_5")]
+    mux.set_canvas_size(8, 8).unwrap();
+
+    assert_eq!(mux.frame(1).unwrap().unwrap().info, info);
+    assert!(mux.delete_frame(0).unwrap());
+    assert!(mux.frame(2).unwrap().is_none());
+    assert!(!mux.delete_frame(2).unwrap());
+
+    let webp = mux.assemble().unwrap();
+    let mux = Mux::from_bytes(&webp).unwrap();
+    assert_ne!(mux.features().unwrap() & ANIMATION_FLAG, 0);
+    assert_eq!(mux.animation_params().unwrap(), Some(params));
+    assert_eq!(mux.frame(1).unwrap().unwrap().info, info);
+}
+
+#[test]
+fn test_errors() {
+    assert!(matches!(
+        Mux::from_bytes(b"RIFF\x04\x00\x00\x00WEBP"),
+        Err(Error::Mux(MuxError::BadData))
+    ));
+    let mut mux = Mux::new().unwrap();
+    assert!(matches!(
+        mux.set_chunk(b"ANMF", b""),
+        Err(Error::Mux(MuxError::InvalidArgument))
+    ));
+    assert!(mux.animation_params().unwrap().is_none());
+}
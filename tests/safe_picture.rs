@@ -0,0 +1,61 @@
+#![cfg(feature = "safe")]
+
+use libwebp_sys::safe::{Config, Decoder, Encoder, Picture, PixelLayout};
+
+fn checkerboard(width: u32, height: u32) -> Picture<'static> {
+    let mut rgba = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let alpha = if (x + y) % 2 == 0 { 255 } else { 0 };
+            rgba.extend_from_slice(&[x as u8, y as u8, 0, alpha]);
+        }
+    }
+    Picture::from_pixels(&rgba, PixelLayout::Rgba, width, height).unwrap()
+}
+
+#[test]
+fn test_crop_rescale_copy() {
+    let mut picture = checkerboard(8, 6);
+    assert!(picture.is_argb());
+    assert!(!picture.is_view());
+    assert!(picture.crop(4, 4, 8, 8).is_err());
+    picture.crop(2, 1, 4, 4).unwrap();
+    assert_eq!((picture.width(), picture.height()), (4, 4));
+    assert_eq!(picture.argb().unwrap()[0], 0x00020100);
+
+    let mut copy = picture.copy().unwrap();
+    copy.rescale(8, 0).unwrap();
+    assert_eq!((copy.width(), copy.height()), (8, 8));
+    assert_eq!((picture.width(), picture.height()), (4, 4));
+}
+
+#[test]
+fn test_view_and_encode() {
+    let mut picture = checkerboard(8, 6);
+    let mut view = picture.view(2, 2, 4, 4).unwrap();
+    assert!(view.is_view());
+    assert_eq!(view.argb().unwrap()[0], 0xFF020200);
+    let webp = Encoder::new(Config::new_lossless())
+        .encode_picture(&mut view)
+        .unwrap();
+    drop(view);
+    let image = Decoder::new().decode(&webp).unwrap();
+    assert_eq!((image.width(), image.height()), (4, 4));
+    assert_eq!(picture.width(), 8);
+}
+
+#[test]
+fn test_transparency_and_conversions() {
+    let mut picture = checkerboard(4, 4);
+    assert!(picture.has_transparency());
+    picture.cleanup_transparent_area();
+    picture.blend_alpha(0xFFFFFF);
+    assert!(!picture.has_transparency());
+
+    picture.argb_to_yuva().unwrap();
+    assert!(!picture.is_argb());
+    assert!(picture.argb().is_none());
+    picture.yuva_to_argb().unwrap();
+    assert!(picture.is_argb());
+    assert!(picture.argb().unwrap().iter().all(|&p| p >> 24 == 0xFF));
+}
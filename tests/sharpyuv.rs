@@ -0,0 +1,60 @@
+#![cfg(all(feature = "sharpyuv", not(feature = "runtime-loading")))]
+
+use std::os::raw::c_void;
+
+use libwebp_sys::{
+    SharpYuvColorSpace, SharpYuvComputeConversionMatrix, SharpYuvConvert,
+    SharpYuvGetConversionMatrix, kSharpYuvMatrixRec709Full, kSharpYuvMatrixWebp,
+    kSharpYuvRangeFull,
+};
+
+#[test]
+fn test_compute_conversion_matrix() {
+    unsafe {
+        let color_space = SharpYuvColorSpace {
+            kr: 0.2126,
+            kb: 0.0722,
+            bit_depth: 8,
+            range: kSharpYuvRangeFull,
+        };
+        let mut matrix = std::mem::zeroed();
+        SharpYuvComputeConversionMatrix(&color_space, &mut matrix);
+        let predefined = &*SharpYuvGetConversionMatrix(kSharpYuvMatrixRec709Full);
+        assert_eq!(matrix.rgb_to_y, predefined.rgb_to_y);
+        assert_eq!(matrix.rgb_to_u, predefined.rgb_to_u);
+        assert_eq!(matrix.rgb_to_v, predefined.rgb_to_v);
+    }
+}
+
+#[test]
+fn test_convert() {
+    let (width, height) = (4, 4);
+    let rgb = [0x80_u8; 4 * 4 * 3];
+    let mut y = [0_u8; 4 * 4];
+    let mut u = [0_u8; 2 * 2];
+    let mut v = [0_u8; 2 * 2];
+    unsafe {
+        let ok = SharpYuvConvert(
+            rgb.as_ptr() as *const c_void,
+            rgb.as_ptr().add(1) as *const c_void,
+            rgb.as_ptr().add(2) as *const c_void,
+            3,
+            width * 3,
+            8,
+            y.as_mut_ptr() as *mut c_void,
+            width,
+            u.as_mut_ptr() as *mut c_void,
+            width / 2,
+            v.as_mut_ptr() as *mut c_void,
+            width / 2,
+            8,
+            width,
+            height,
+            SharpYuvGetConversionMatrix(kSharpYuvMatrixWebp),
+        );
+        assert!(ok != 0);
+    }
+    // Gray stays gray.
+    assert!(u.iter().chain(&v).all(|&c| c.abs_diff(128) <= 1));
+    assert!(y.iter().all(|&l| l == y[0]));
+}
@@ -0,0 +1,15 @@
+#![cfg(not(feature = "runtime-loading"))]
+
+use libwebp_sys::threads_available;
+
+#[test]
+fn test_threads_available() {
+    if option_env!("__LIBWEBP_SYS_BUNDLED").is_some() {
+        assert_eq!(
+            threads_available(),
+            Some(cfg!(feature = "threads") && !cfg!(target_family = "wasm"))
+        );
+    } else {
+        assert_eq!(threads_available(), None);
+    }
+}
@@ -1,3 +1,5 @@
+#![cfg(not(feature = "runtime-loading"))]
+
 #[cfg(feature = "1_1")]
 #[test]
 fn test_malloc() {